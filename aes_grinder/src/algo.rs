@@ -1,5 +1,6 @@
 //! Struc Algo permettant de représenter des Algo
 use crate::matrix::Matrix;
+use crate::variable::Variable;
 use core::cmp::Ordering;
 use std::collections::hash_map::DefaultHasher;
 use std::fs::File;
@@ -13,7 +14,7 @@ use std::{
 
 #[derive(Eq, Clone, Debug)]
 pub struct Algo {
-    pub vars: HashSet<String>,
+    pub vars: HashSet<Variable>,
     pub time: usize,
     pub memory: u32,
     pub nb_solutions: usize,
@@ -79,12 +80,12 @@ impl Algo {
 
         str_to_build.push('{');
         if let Some(var) = iter_vars.next() {
-            str_to_build.push_str(var);
+            str_to_build.push_str(&var.to_string());
         }
         for var in iter_vars {
             str_to_build.push(',');
             str_to_build.push(' ');
-            str_to_build.push_str(var);
+            str_to_build.push_str(&var.to_string());
         }
         str_to_build.push('}');
     }
@@ -100,14 +101,10 @@ impl Algo {
         let mut mark_son_left = None;
         let mut mark_son_right = None;
 
-        let not_vars: Vec<String> = matrix.get_all_variables().iter().filter(|v| {
-            let mut v = v.as_str();
-            if v.contains("S(") {
-                //trim to get v such as S(v)
-                v = &v[2..v.len() - 1];
-            }
-            !self.get_all_variables().contains(v)
-        }).cloned().collect();
+        let not_vars: Vec<Variable> = matrix.get_all_variables().into_iter().filter(|v| {
+            //S(v) goes with v
+            !self.get_all_variables().contains(&v.input())
+        }).collect();
         let mut matrix_scaled = matrix.clone();
         matrix_scaled.scale_on(not_vars.clone());
         let m_str = matrix_scaled.to_dot_string();
@@ -234,8 +231,8 @@ impl Algo {
     }
 
     ///Constructeur d'un base solver
-    pub fn base_solver(var: String) -> Algo {
-        let mut vars = HashSet::<String>::new();
+    pub fn base_solver(var: Variable) -> Algo {
+        let mut vars = HashSet::<Variable>::new();
         vars.insert(var);
         Algo {
             vars: vars.clone(),
@@ -249,11 +246,11 @@ impl Algo {
 
     ///Fonction de fusion de deux algo
     pub fn fusion_two_algo(a1: Box<Algo>, a2: Box<Algo>, matrix: &mut Matrix) -> Algo {
-        let vars1: HashSet<String> = a1.vars.clone().into_iter().collect();
-        let vars2: HashSet<String> = a2.vars.clone().into_iter().collect();
+        let vars1: HashSet<Variable> = a1.vars.clone().into_iter().collect();
+        let vars2: HashSet<Variable> = a2.vars.clone().into_iter().collect();
         let union_vars = vars1.union(&vars2);
         //Remove duplicates
-        let union_vars: HashSet<String> = union_vars.cloned().collect();
+        let union_vars: HashSet<Variable> = union_vars.cloned().collect();
         assert!(
             union_vars.len() <= 40,
             "Error: too many variables a1 vars = {:?}\n a2 vars = {:?}\n union = {:?} ",
@@ -301,8 +298,8 @@ impl Algo {
         }
     }
 
-    pub fn get_all_variables(&self) -> HashSet<String> {
-        self.vars.iter().cloned().collect::<HashSet<String>>()
+    pub fn get_all_variables(&self) -> HashSet<Variable> {
+        self.vars.iter().cloned().collect::<HashSet<Variable>>()
     }
 
     pub fn get_time_complexity(&self) -> usize {
@@ -316,11 +313,12 @@ mod tests {
     use std::process::Command;
 
     use super::*;
+    use crate::variable::var;
 
     #[test]
     fn compare_algo() {
         let algo_sad = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 100,
             memory: 100,
             nb_solutions: 20,
@@ -328,7 +326,7 @@ mod tests {
             son2: None,
         };
         let algo_good = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
@@ -341,7 +339,7 @@ mod tests {
     #[test]
     fn compare_algo_time() {
         let algo_sad = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 2,
             memory: 1,
             nb_solutions: 1,
@@ -349,7 +347,7 @@ mod tests {
             son2: None,
         };
         let algo_good = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
@@ -363,7 +361,7 @@ mod tests {
     #[test]
     fn compare_algo_memory_for_same_time() {
         let algo_sad = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 1,
             memory: 2,
             nb_solutions: 1,
@@ -371,7 +369,7 @@ mod tests {
             son2: None,
         };
         let algo_good = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
@@ -384,7 +382,7 @@ mod tests {
     #[test]
     fn compare_algo_time_and_memory() {
         let algo_sad = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 2,
             memory: 2,
             nb_solutions: 1,
@@ -392,7 +390,7 @@ mod tests {
             son2: None,
         };
         let algo_good = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
@@ -406,7 +404,7 @@ mod tests {
     #[test]
     fn compare_algo_nb_solution() {
         let algo_sad = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 1,
             memory: 1,
             nb_solutions: 2,
@@ -414,7 +412,7 @@ mod tests {
             son2: None,
         };
         let algo_good = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
@@ -429,7 +427,7 @@ mod tests {
     fn test_number_solutions() {
         println!("Test number solutions");
         let matrix = Matrix::from(vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]]);
-        let algo = Algo::base_solver("X_1".to_string());
+        let algo = Algo::base_solver(var("X_1"));
         println!("After num sol\n{}", matrix);
         assert_eq!(1, algo.nb_solutions);
     }
@@ -437,7 +435,7 @@ mod tests {
     #[test]
     fn to_dot_00() -> std::io::Result<()> {
         let algo_good = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
@@ -461,7 +459,7 @@ mod tests {
     #[test]
     fn test_compare1() {
         let algo1 = Algo {
-            vars: HashSet::<String>::from(["x".to_string(), "y".to_string()]),
+            vars: HashSet::<Variable>::from([var("x"), var("y")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
@@ -469,7 +467,7 @@ mod tests {
             son2: None,
         };
         let algo2 = Algo {
-            vars: HashSet::<String>::from(["x".to_string(), "y".to_string()]),
+            vars: HashSet::<Variable>::from([var("x"), var("y")]),
             time: 3,
             memory: 1,
             nb_solutions: 1,
@@ -483,7 +481,7 @@ mod tests {
     #[test]
     fn to_dot_01() -> std::io::Result<()> {
         let left = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
@@ -491,7 +489,7 @@ mod tests {
             son2: None,
         };
         let right = Algo {
-            vars: HashSet::<String>::from(["x".to_string(), "y".to_string()]),
+            vars: HashSet::<Variable>::from([var("x"), var("y")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
@@ -499,7 +497,7 @@ mod tests {
             son2: None,
         };
         let root = Algo {
-            vars: HashSet::<String>::from(["x".to_string(), "y".to_string()]),
+            vars: HashSet::<Variable>::from([var("x"), var("y")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
@@ -523,7 +521,7 @@ mod tests {
     #[test]
     fn to_dot_02() -> std::io::Result<()> {
         let c1_left = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
@@ -531,7 +529,7 @@ mod tests {
             son2: None,
         };
         let c1_right = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
@@ -539,7 +537,7 @@ mod tests {
             son2: None,
         };
         let c0_left = Algo {
-            vars: HashSet::<String>::from(["x".to_string(), "y".to_string()]),
+            vars: HashSet::<Variable>::from([var("x"), var("y")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
@@ -547,7 +545,7 @@ mod tests {
             son2: Some(Box::new(c1_right)),
         };
         let c0_right = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
@@ -555,7 +553,7 @@ mod tests {
             son2: None,
         };
         let root = Algo {
-            vars: HashSet::<String>::from(["x".to_string()]),
+            vars: HashSet::<Variable>::from([var("x")]),
             time: 1,
             memory: 1,
             nb_solutions: 1,
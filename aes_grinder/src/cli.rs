@@ -1,4 +1,4 @@
-use clap::Parser as ClapParser;
+use clap::{Parser as ClapParser, Subcommand};
 
 #[derive(ClapParser)]
 #[command(name = "aes_grinder")]
@@ -8,4 +8,41 @@ use clap::Parser as ClapParser;
 pub struct Cli {
     #[command(flatten)]
     verbose: clap_verbosity_flag::Verbosity,
+
+    /// Keep parsing after an error and report every problem of the system
+    #[arg(long)]
+    pub all_errors: bool,
+
+    /// Name of the section to process, the first one by default
+    #[arg(long, conflicts_with = "all_sections")]
+    pub section: Option<String>,
+
+    /// Process every section of the file, one after the other
+    #[arg(long)]
+    pub all_sections: bool,
+
+    /// Write the systems left by the elimination of the linear variables
+    #[arg(long, value_name = "FILE")]
+    pub save_reduced: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Rewrite system files in place in the canonical form, files using
+    /// comments, for, let, const, --include or --use are refused
+    Format {
+        #[arg(required = true)]
+        files: Vec<String>,
+
+        /// Group the rows by round under a comment
+        #[arg(long)]
+        group_by_round: bool,
+    },
+
+    /// Print, as a system file, what the first system of each file has and
+    /// the other one has not, exit with 1 if they differ and 2 on errors
+    Diff { left: String, right: String },
 }
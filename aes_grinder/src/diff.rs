@@ -0,0 +1,284 @@
+//! Differences between the solutions of two systems, as .eqs text
+use crate::field::GF2n;
+use crate::matrix::{Equation, Matrix};
+use crate::utils::Number;
+use crate::variable::Variable;
+use crate::writer::write_equation;
+use std::fmt::Write;
+
+/// What a system of the diff has and the other one has not
+pub struct Side {
+    pub variables: Vec<Variable>, // Variables absent of the other system, sorted
+    pub equations: Vec<Equation>, // Reduced equations the other system does not imply
+}
+
+/// Differences between a left and a right system, a variable of only one
+/// system takes any value in the other one
+pub struct SystemDiff {
+    field: GF2n,
+    pub left: Side,
+    pub right: Side,
+}
+
+impl SystemDiff {
+    /// Diff of systems in the same field
+    pub fn new(left: &Matrix, right: &Matrix) -> Self {
+        let side = |ours: &Matrix, theirs: &Matrix| {
+            let mut variables: Vec<Variable> = ours
+                .get_all_variables()
+                .into_iter()
+                .filter(|var| !theirs.get_vars_map().contains_key(var))
+                .collect();
+            variables.sort();
+            Side {
+                variables,
+                equations: ours.not_implied_by(theirs),
+            }
+        };
+        SystemDiff {
+            field: left.field().clone(),
+            left: side(left, right),
+            right: side(right, left),
+        }
+    }
+
+    /// True if the systems have the same variables and the same solutions
+    pub fn is_empty(&self) -> bool {
+        [&self.left, &self.right]
+            .iter()
+            .all(|side| side.variables.is_empty() && side.equations.is_empty())
+    }
+
+    /// The diff as a .eqs file, the reduced equations of each system the
+    /// other one does not imply in a section, after a comment when the other
+    /// one has an equation only different by the indices of its variables
+    pub fn to_eqs(&self, left_name: &str, right_name: &str) -> String {
+        let mut text = String::new();
+        if self.field != GF2n::aes() {
+            writeln!(text, "--field: {}", self.field).unwrap();
+        }
+        if self.is_empty() {
+            writeln!(
+                text,
+                "# {} and {} have the same solutions",
+                left_name, right_name
+            )
+            .unwrap();
+            return text;
+        }
+
+        let sides = [
+            (left_name, &self.left, right_name, &self.right),
+            (right_name, &self.right, left_name, &self.left),
+        ];
+        for (name, side, _, _) in sides {
+            if !side.variables.is_empty() {
+                let vars: Vec<String> = side.variables.iter().map(|var| var.to_string()).collect();
+                writeln!(text, "# variables only in {}: {}", name, vars.join(" ")).unwrap();
+            }
+        }
+        for (name, side, other_name, other) in sides {
+            if side.equations.is_empty() {
+                continue;
+            }
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            writeln!(text, "--only in {}:", name).unwrap();
+            for equation in &side.equations {
+                if let Some(permuted) = other
+                    .equations
+                    .iter()
+                    .find(|other| same_but_indices(equation, other))
+                {
+                    writeln!(
+                        text,
+                        "# with other indices in {}: {}",
+                        other_name,
+                        write_equation(permuted)
+                    )
+                    .unwrap();
+                }
+                writeln!(text, "{}", write_equation(equation)).unwrap();
+            }
+        }
+        text
+    }
+}
+
+/// True if the equations have the same terms once the indices of b are
+/// renamed, each index position by its own one to one renaming, as two
+/// rows of ShiftRows with other offsets
+fn same_but_indices(a: &Equation, b: &Equation) -> bool {
+    let mut others: Vec<Option<&(Variable, Number)>> = b.terms.iter().map(Some).collect();
+    a.rhs == b.rhs && a.terms.len() == b.terms.len() && renamed(&a.terms, &mut others, &mut vec![])
+}
+
+/// True if each term is a term of others once renamed, the terms found
+/// are taken out of others and the renaming holds (position, from, to)
+fn renamed(
+    terms: &[(Variable, Number)],
+    others: &mut [Option<&(Variable, Number)>],
+    renaming: &mut Vec<(usize, usize, usize)>,
+) -> bool {
+    let Some(((var, coef), rest)) = terms.split_first() else {
+        return true;
+    };
+    for k in 0..others.len() {
+        let Some(other) = others[k] else {
+            continue;
+        };
+        if other.1 != *coef
+            || other.0.without_indices() != var.without_indices()
+            || other.0.indices().len() != var.indices().len()
+        {
+            continue;
+        }
+        let len = renaming.len();
+        if rename(var.indices(), other.0.indices(), renaming) {
+            others[k] = None;
+            if renamed(rest, others, renaming) {
+                return true;
+            }
+            others[k] = Some(other);
+        }
+        renaming.truncate(len);
+    }
+    false
+}
+
+/// Add the renaming of the indices from into the indices to, false if it
+/// breaks a renaming already made
+fn rename(from: &[usize], to: &[usize], renaming: &mut Vec<(usize, usize, usize)>) -> bool {
+    for (position, (&from, &to)) in from.iter().zip(to).enumerate() {
+        match renaming
+            .iter()
+            .find(|&&(p, f, t)| p == position && (f == from || t == to))
+        {
+            Some(&(_, f, t)) if (f, t) != (from, to) => return false,
+            Some(_) => {}
+            None => renaming.push((position, from, to)),
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::variable::var;
+    use crate::GlobalInfos;
+
+    fn parse(system: &str) -> Matrix {
+        let mut global_infos = GlobalInfos::new(String::new());
+        Parser::from_string(system, &global_infos)
+            .parse_system(&mut global_infos)
+            .unwrap()
+    }
+
+    #[test]
+    fn same_solutions() {
+        let left = parse("--system:\nK[0] + X[0]\nK[1] + X[1]\nX[0] + Y = 1\n");
+        let right = parse("--system:\nK[1] + X[1]\nK[0] + Y = 1\n02*X[0] + 02*K[0]\n");
+
+        let diff = SystemDiff::new(&left, &right);
+        assert!(diff.is_empty());
+        assert_eq!(
+            diff.to_eqs("left.eqs", "right.eqs"),
+            "# left.eqs and right.eqs have the same solutions\n"
+        );
+    }
+
+    #[test]
+    fn differences() {
+        let left = parse("--system:\nK[0] + X[1]\nK[1] + X[1]\nX[0] + Y = 1\n");
+        let right = parse("--system:\nK[1] + X[2]\nK[1] + X[1]\nX[0] + Y = 1\nZ = 2\n");
+
+        let diff = SystemDiff::new(&left, &right);
+        assert!(!diff.is_empty());
+        let text = diff.to_eqs("left.eqs", "right.eqs");
+        assert_eq!(
+            text,
+            "# variables only in left.eqs: K[0]\n\
+             # variables only in right.eqs: X[2] Z\n\
+             \n\
+             --only in left.eqs:\n\
+             # with other indices in right.eqs: K[1] + X[2]\n\
+             K[0] + X[1]\n\
+             \n\
+             --only in right.eqs:\n\
+             # with other indices in left.eqs: K[0] + X[1]\n\
+             K[1] + X[2]\n\
+             X[1] + X[2]\n\
+             Z = 0x02\n"
+        );
+
+        // The diff is a system file
+        let mut global_infos = GlobalInfos::new(String::new());
+        let sections = Parser::from_string(&text, &global_infos)
+            .parse_sections(&mut global_infos)
+            .unwrap();
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[1].0, "only in right.eqs");
+        assert_eq!(sections[1].1.get_row_number(), 3);
+    }
+
+    #[test]
+    fn same_span() {
+        // The same lines written in two ways give the same diff
+        let left = parse("--system:\na + b\nb + c = 1\n");
+        let rewritten = parse("--system:\na + c = 1\n02*a + 02*b\n");
+        let right = parse("--system:\na + b\n");
+
+        let text = SystemDiff::new(&left, &right).to_eqs("left.eqs", "right.eqs");
+        assert_eq!(
+            text,
+            SystemDiff::new(&rewritten, &right).to_eqs("left.eqs", "right.eqs")
+        );
+        assert_eq!(
+            text,
+            "# variables only in left.eqs: c\n\n--only in left.eqs:\na + c = 0x01\nb + c = 0x01\n"
+        );
+    }
+
+    #[test]
+    fn other_indices() {
+        let equation = |terms: &[&str], rhs: u8| Equation {
+            terms: terms
+                .iter()
+                .map(|name| (var(name), Number::from(1)))
+                .collect(),
+            rhs: Number::from(rhs),
+        };
+
+        // one offset on the columns
+        assert!(same_but_indices(
+            &equation(&["W[0,1]", "X[0,1]", "X[1,2]"], 0),
+            &equation(&["W[0,2]", "X[0,2]", "X[1,3]"], 0)
+        ));
+        // K and X with other offsets
+        assert!(!same_but_indices(
+            &equation(&["K[0]", "X[0]"], 0),
+            &equation(&["K[3]", "X[7]"], 0)
+        ));
+        // two indices renamed into one
+        assert!(!same_but_indices(
+            &equation(&["X[0]", "Y[1]"], 0),
+            &equation(&["X[2]", "Y[2]"], 0)
+        ));
+        assert!(!same_but_indices(
+            &equation(&["K[0]"], 0),
+            &equation(&["K[1]"], 1)
+        ));
+    }
+
+    #[test]
+    fn field() {
+        let left = parse("--field: GF(2^4) / 0x13\n--system:\na + b\n");
+        let right = parse("--field: GF(2^4) / 0x13\n--system:\na + 0xf*b\n");
+
+        let text = SystemDiff::new(&left, &right).to_eqs("left.eqs", "right.eqs");
+        assert!(text.starts_with("--field: GF(2^4) / 0x13\n\n--only in left.eqs:\na + b\n"));
+    }
+}
@@ -23,8 +23,8 @@ pub fn random_search(matrix: &mut Matrix) -> Box<Algo> {
     let mut lst_algo: Vec<Box<Algo>> = vec![];
 
     for x_var in matrix.get_all_variables() {
-        //We create a base solver for each variables for variables that are not S(x)
-        if x_var.contains('(') {
+        //We create a base solver for each unknown variables that are not S(x)
+        if x_var.is_nonlinear() || matrix.is_known(&x_var) {
             continue;
         }
         lst_algo.push(Box::new(Algo::base_solver(x_var)));
@@ -60,8 +60,8 @@ pub fn search_best_multiple_random(matrix: &mut Matrix, nb_algo: usize) -> Box<A
 fn generate_all_base_solver(x: &Matrix) -> HashSet<Box<Algo>> {
     let mut g: HashSet<Box<Algo>> = HashSet::new();
     for x_var in x.get_all_variables() {
-        //We create a base solver for each variables for variables that are not S(x)
-        if x_var.contains('(') {
+        //We create a base solver for each unknown variables that are not S(x)
+        if x_var.is_nonlinear() || x.is_known(&x_var) {
             continue;
         }
         g.insert(Box::new(Algo::base_solver(x_var)));
@@ -88,6 +88,9 @@ pub fn exhaustive_search(x: &mut Matrix, time_complexity: usize) -> HashSet<Box<
     //Set of pair of algo
     let mut p: HashSet<(Box<Algo>, Box<Algo>)> = set_of_pair_of_algo(&g);
 
+    //Stop once an algo recovers every target
+    let targets = x.get_target_variables();
+
     // While g dont contains an algo with 20 variables
     let mut i = 0;
     while !p.is_empty() {
@@ -103,6 +106,11 @@ pub fn exhaustive_search(x: &mut Matrix, time_complexity: usize) -> HashSet<Box<
         let c = Box::new(Algo::fusion_two_algo(a1.clone(), a2.clone(), x));
         if c.get_time_complexity() <= time_complexity {
             update_queue(&mut g, &mut p, c);
+            if !targets.is_empty()
+                && g.iter().any(|a| targets.iter().all(|t| a.vars.contains(t)))
+            {
+                break;
+            }
         } else {
             println!("Time complexity reached")
         }
@@ -194,17 +202,17 @@ mod test_exhaustive {
 
     use std::collections::HashMap;
 
-    use crate::{parser::Parser, GlobalInfos};
+    use crate::{parser::Parser, variable::{var, Variable}, GlobalInfos};
 
     use super::*;
 
     #[test]
     fn test_exhaustive1() {
         let mut matrix = Matrix::from(vec![vec![1, 2, 3], vec![4, 3, 2], vec![4, 8, 2]]);
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("A".to_string(), 0);
-        vars_maps.insert("B".to_string(), 1);
-        vars_maps.insert("C".to_string(), 2);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("A"), 0);
+        vars_maps.insert(var("B"), 1);
+        vars_maps.insert(var("C"), 2);
         matrix.set_vars_map(vars_maps.clone());
         exhaustive_search(&mut matrix, 50);
     }
@@ -216,16 +224,16 @@ mod test_exhaustive {
             vec![0, 0, 0, 1],
             vec![0, 7, 0, 1],
         ]);
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("A".to_string(), 0);
-        vars_maps.insert("B".to_string(), 1);
-        vars_maps.insert("C".to_string(), 2);
-        vars_maps.insert("D".to_string(), 3);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("A"), 0);
+        vars_maps.insert(var("B"), 1);
+        vars_maps.insert(var("C"), 2);
+        vars_maps.insert(var("D"), 3);
         matrix.set_vars_map(vars_maps.clone());
-        let c1 = Algo::base_solver("A".to_string());
-        let c2 = Algo::base_solver("B".to_string());
-        let c3 = Algo::base_solver("C".to_string());
-        let c4 = Algo::base_solver("D".to_string());
+        let c1 = Algo::base_solver(var("A"));
+        let c2 = Algo::base_solver(var("B"));
+        let c3 = Algo::base_solver(var("C"));
+        let c4 = Algo::base_solver(var("D"));
         let c = Algo::fusion_two_algo(Box::new(c1.clone()), Box::new(c2.clone()), &mut matrix);
 
         let mut g: HashSet<Box<Algo>> = HashSet::new();
@@ -250,21 +258,21 @@ mod test_exhaustive {
     fn test_generate_all_base_solver() {
         let system: &str = "equation_system/1r_3.txt";
         let mut globals: GlobalInfos = GlobalInfos::new(system.to_owned());
-        let mut parser_mod = Parser::new(&globals);
+        let mut parser_mod = Parser::new(&globals).unwrap();
         let mut matrix = parser_mod
             .parse_system(&mut globals)
             .expect("Error while parsing system");
         matrix.set_vars_map(parser_mod.vars_map);
         
         let g = generate_all_base_solver(&matrix);
-        assert_eq!(g.len(), matrix.get_all_variables().iter().filter(|x| !x.contains("S(")).count());
+        assert_eq!(g.len(), matrix.get_all_variables().iter().filter(|x| !x.is_nonlinear()).count());
     }
 
     #[test]
     fn test_keep_better() {
         let system: &str = "equation_system/1r_3.txt";
         let mut globals: GlobalInfos = GlobalInfos::new(system.to_owned());
-        let mut parser_mod = Parser::new(&globals);
+        let mut parser_mod = Parser::new(&globals).unwrap();
         let mut matrix = parser_mod
             .parse_system(&mut globals)
             .expect("Error while parsing system");
@@ -272,7 +280,7 @@ mod test_exhaustive {
         
         let g = generate_all_base_solver(&matrix);
         let c = Algo {
-            vars: HashSet::from([matrix.get_all_variables().iter().filter(|x| !x.contains("S(")).next().unwrap().clone()]),
+            vars: HashSet::from([matrix.get_all_variables().iter().filter(|x| !x.is_nonlinear()).next().unwrap().clone()]),
             time: 100,
             memory: 10,
             nb_solutions: 10,
@@ -285,7 +293,7 @@ mod test_exhaustive {
         assert_eq!(g, gprim);
 
         let c = Algo {
-            vars: HashSet::from([matrix.get_all_variables().iter().filter(|x| !x.contains("S(")).next().unwrap().clone()]),
+            vars: HashSet::from([matrix.get_all_variables().iter().filter(|x| !x.is_nonlinear()).next().unwrap().clone()]),
             time: 0,
             memory: 10,
             nb_solutions: 10,
@@ -302,7 +310,7 @@ mod test_exhaustive {
     fn test_keep_all_pair_that_are_better() {
         let system: &str = "equation_system/1r_3.txt";
         let mut globals: GlobalInfos = GlobalInfos::new(system.to_owned());
-        let mut parser_mod = Parser::new(&globals);
+        let mut parser_mod = Parser::new(&globals).unwrap();
         let mut matrix = parser_mod
             .parse_system(&mut globals)
             .expect("Error while parsing system");
@@ -310,7 +318,7 @@ mod test_exhaustive {
         
         let g = generate_all_base_solver(&matrix);
         let c = Algo {
-            vars: HashSet::from([matrix.get_all_variables().iter().filter(|x| !x.contains("S(")).next().unwrap().clone()]),
+            vars: HashSet::from([matrix.get_all_variables().iter().filter(|x| !x.is_nonlinear()).next().unwrap().clone()]),
             time: 100,
             memory: 10,
             nb_solutions: 10,
@@ -324,7 +332,7 @@ mod test_exhaustive {
         assert_eq!(p.len(), pprim.len());
 
         let c = Algo {
-            vars: HashSet::from([matrix.get_all_variables().iter().filter(|x| !x.contains("S(")).next().unwrap().clone()]),
+            vars: HashSet::from([matrix.get_all_variables().iter().filter(|x| !x.is_nonlinear()).next().unwrap().clone()]),
             time: 0,
             memory: 10,
             nb_solutions: 10,
@@ -341,7 +349,7 @@ mod test_exhaustive {
     fn test_add_new_pairs_no_doublon() {
         let system: &str = "equation_system/1r_3.txt";
         let mut globals: GlobalInfos = GlobalInfos::new(system.to_owned());
-        let mut parser_mod = Parser::new(&globals);
+        let mut parser_mod = Parser::new(&globals).unwrap();
         let mut matrix = parser_mod
             .parse_system(&mut globals)
             .expect("Error while parsing system");
@@ -349,7 +357,7 @@ mod test_exhaustive {
         
         let mut g = generate_all_base_solver(&matrix);
         let c = Algo {
-            vars: HashSet::from([matrix.get_all_variables().iter().filter(|x| !x.contains("S(")).next().unwrap().clone()]),
+            vars: HashSet::from([matrix.get_all_variables().iter().filter(|x| !x.is_nonlinear()).next().unwrap().clone()]),
             time: 100,
             memory: 10,
             nb_solutions: 10,
@@ -0,0 +1,382 @@
+//! Finite fields of the coefficients of the systems
+use crate::utils::Number;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::sync::{Arc, OnceLock};
+
+/// Field of characteristic 2 the coefficients live in, the elements are
+/// plain values and every operation goes through the field
+/// In characteristic 2 the subtraction is the addition
+pub trait Field: Clone + Debug + PartialEq {
+    type Elem: Copy + Debug + Display + Eq + Ord + Hash;
+
+    fn zero(&self) -> Self::Elem;
+    fn one(&self) -> Self::Elem;
+    fn add(&self, a: Self::Elem, b: Self::Elem) -> Self::Elem;
+    fn mul(&self, a: Self::Elem, b: Self::Elem) -> Self::Elem;
+
+    /// Multiplicative inverse, None for zero
+    fn inv(&self, a: Self::Elem) -> Option<Self::Elem>;
+
+    /// Element written as value in the files, None if it is not in the field
+    fn elem(&self, value: u32) -> Option<Self::Elem>;
+
+    /// Value of the element as written in the files
+    fn value(&self, a: Self::Elem) -> u32;
+
+    /// Number of elements of the field
+    fn order(&self) -> u32;
+
+    /// row = c * row
+    fn scale_slice(&self, row: &mut [Self::Elem], c: Self::Elem) {
+        row.iter_mut().for_each(|x| *x = self.mul(c, *x));
+    }
+
+    /// dst = dst + c * src, the row operation of the eliminations
+    fn mul_add_slice(&self, dst: &mut [Self::Elem], c: Self::Elem, src: &[Self::Elem]) {
+        dst.iter_mut()
+            .zip(src)
+            .for_each(|(x, &y)| *x = self.add(*x, self.mul(c, y)));
+    }
+}
+
+/// GF(2^n) / polynomial for n from 1 to 16, an element is a polynomial
+/// over GF(2) of degree below n, stored as the integer of its coefficients
+/// Multiplications use exp/log tables of a generator, shared by the clones,
+/// and up to GF(2^8) a table of every product for the row operations
+#[derive(Clone)]
+pub struct GF2n {
+    tables: Arc<Tables>,
+}
+
+struct Tables {
+    degree: u32,
+    polynomial: u32,
+    exp: Vec<u16>, // g^i for i up to twice the order of g, no modulo for the products
+    log: Vec<u16>, // log[g^i] = i, log[0] is not used
+    inv: Vec<u16>, // inv[0] = 0
+    mul: Vec<u16>, // mul[a << degree | b] = a * b, empty above GF(2^8)
+}
+
+impl GF2n {
+    /// Field GF(2^n) / polynomial, the polynomial need to be irreducible
+    /// and of degree n from 1 to 16
+    pub fn new(polynomial: u32) -> Result<Self, String> {
+        let degree = degree(polynomial);
+        if !(1..=16).contains(&degree) {
+            return Err(format!(
+                "polynomial {:#x} is of degree {}, fields are GF(2^n) with n from 1 to 16",
+                polynomial, degree
+            ));
+        }
+        if !is_irreducible(polynomial) {
+            return Err(format!("polynomial {:#x} is not irreducible", polynomial));
+        }
+
+        let order = 1u32 << degree;
+        let mul = |a: u32, b: u32| mul_slow(a, b, polynomial);
+        // x is not always a generator, as for 0x11b
+        let exp = (1..order)
+            .find_map(|generator| {
+                let mut exp = vec![1u16];
+                let mut power = generator;
+                while power != 1 {
+                    exp.push(power as u16);
+                    power = mul(power, generator);
+                }
+                (exp.len() as u32 == order - 1).then_some(exp)
+            })
+            .unwrap();
+
+        let mut log = vec![0u16; order as usize];
+        for (i, &power) in exp.iter().enumerate() {
+            log[power as usize] = i as u16;
+        }
+        let inv = (0..order as usize)
+            .map(|a| match a {
+                0 => 0,
+                _ => exp[(exp.len() - log[a] as usize) % exp.len()],
+            })
+            .collect();
+        let exp: Vec<u16> = exp.iter().chain(exp.iter()).copied().collect();
+        // 2^16 entries for GF(2^8), too many above
+        let mul = match degree {
+            1..=8 => (0..order * order)
+                .map(|ab| match (ab >> degree, ab & (order - 1)) {
+                    (0, _) | (_, 0) => 0,
+                    (a, b) => exp[log[a as usize] as usize + log[b as usize] as usize],
+                })
+                .collect(),
+            _ => vec![],
+        };
+
+        Ok(GF2n {
+            tables: Arc::new(Tables {
+                degree,
+                polynomial,
+                exp,
+                log,
+                inv,
+                mul,
+            }),
+        })
+    }
+
+    /// GF(2^8) / 0x11b, the field of the AES
+    pub fn aes() -> Self {
+        static AES: OnceLock<GF2n> = OnceLock::new();
+        AES.get_or_init(|| GF2n::new(0x11b).unwrap()).clone()
+    }
+
+    pub fn degree(&self) -> u32 {
+        self.tables.degree
+    }
+
+    pub fn polynomial(&self) -> u32 {
+        self.tables.polynomial
+    }
+
+    /// Products c * b of every b, indexed by b, if the field has the table
+    fn mul_by(&self, c: Number) -> Option<&[u16]> {
+        let tables = &self.tables;
+        let start = (c.get_value() as usize) << tables.degree;
+        tables.mul.get(start..start + (1 << tables.degree))
+    }
+
+    /// Product of the non zero c and b with the logarithm of c
+    fn mul_log(&self, log_c: usize, b: Number) -> Number {
+        match b.get_value() {
+            0 => Number::new(0),
+            b => Number::new(self.tables.exp[log_c + self.tables.log[b as usize] as usize]),
+        }
+    }
+}
+
+impl Field for GF2n {
+    type Elem = Number;
+
+    fn zero(&self) -> Number {
+        Number::new(0)
+    }
+
+    fn one(&self) -> Number {
+        Number::new(1)
+    }
+
+    fn add(&self, a: Number, b: Number) -> Number {
+        a + b
+    }
+
+    fn mul(&self, a: Number, b: Number) -> Number {
+        if a.get_value() == 0 || b.get_value() == 0 {
+            return Number::new(0);
+        }
+        let tables = &self.tables;
+        let log = tables.log[a.get_value() as usize] as usize
+            + tables.log[b.get_value() as usize] as usize;
+        Number::new(tables.exp[log])
+    }
+
+    fn inv(&self, a: Number) -> Option<Number> {
+        match a.get_value() {
+            0 => None,
+            a => Some(Number::new(self.tables.inv[a as usize])),
+        }
+    }
+
+    fn elem(&self, value: u32) -> Option<Number> {
+        (value < self.order()).then(|| Number::new(value as u16))
+    }
+
+    fn value(&self, a: Number) -> u32 {
+        a.get_value() as u32
+    }
+
+    fn order(&self) -> u32 {
+        1 << self.degree()
+    }
+
+    fn scale_slice(&self, row: &mut [Number], c: Number) {
+        if c.get_value() == 0 {
+            row.fill(Number::new(0));
+        } else if let Some(mul) = self.mul_by(c) {
+            row.iter_mut()
+                .for_each(|x| *x = Number::new(mul[x.get_value() as usize]));
+        } else {
+            let log_c = self.tables.log[c.get_value() as usize] as usize;
+            row.iter_mut().for_each(|x| *x = self.mul_log(log_c, *x));
+        }
+    }
+
+    fn mul_add_slice(&self, dst: &mut [Number], c: Number, src: &[Number]) {
+        if c.get_value() == 0 {
+            return;
+        }
+        if let Some(mul) = self.mul_by(c) {
+            dst.iter_mut()
+                .zip(src)
+                .for_each(|(x, y)| *x = *x + Number::new(mul[y.get_value() as usize]));
+        } else {
+            let log_c = self.tables.log[c.get_value() as usize] as usize;
+            dst.iter_mut()
+                .zip(src)
+                .for_each(|(x, &y)| *x = *x + self.mul_log(log_c, y));
+        }
+    }
+}
+
+/// Fields with the same polynomial are the same
+impl PartialEq for GF2n {
+    fn eq(&self, other: &Self) -> bool {
+        self.polynomial() == other.polynomial()
+    }
+}
+
+impl Debug for GF2n {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+/// Same as the --field directive of the .eqs files
+impl Display for GF2n {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "GF(2^{}) / {:#x}", self.degree(), self.polynomial())
+    }
+}
+
+fn degree(polynomial: u32) -> u32 {
+    31u32.saturating_sub(polynomial.leading_zeros())
+}
+
+/// Product of a and b modulo polynomial, bit by bit
+fn mul_slow(mut a: u32, mut b: u32, polynomial: u32) -> u32 {
+    let degree = degree(polynomial);
+    let mut result = 0;
+    while a > 0 {
+        if a & 1 == 1 {
+            result ^= b;
+        }
+        b <<= 1;
+        if b >> degree != 0 {
+            b ^= polynomial;
+        }
+        a >>= 1;
+    }
+    result
+}
+
+/// True if polynomial, over GF(2), has no divisor of lower degree,
+/// which makes GF(2)[x] / polynomial a field
+pub fn is_irreducible(polynomial: u32) -> bool {
+    let n = degree(polynomial);
+    if polynomial < 2 {
+        return false;
+    }
+    // Remainder of the division of polynomial by divisor
+    let remainder = |divisor: u32| {
+        let mut rem = polynomial;
+        while rem != 0 && degree(rem) >= degree(divisor) {
+            rem ^= divisor << (degree(rem) - degree(divisor));
+        }
+        rem
+    };
+    // a divisor of degree above n / 2 goes with one below
+    (2..1u32 << (n / 2 + 1)).all(|divisor| remainder(divisor) != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aes() {
+        let field = GF2n::aes();
+        let n = |value: u16| Number::new(value);
+
+        assert_eq!(field.mul(n(0x57), n(0x83)), n(0xc1));
+        assert_eq!(field.mul(n(0x57), n(0x13)), n(0xfe));
+        assert_eq!(field.inv(n(0x53)), Some(n(0xca)));
+        assert_eq!(field.inv(n(0)), None);
+        assert_eq!(field.to_string(), "GF(2^8) / 0x11b");
+    }
+
+    #[test]
+    fn tables() {
+        // every size, and a polynomial where x is not a generator
+        for polynomial in [0b10, 0b11, 0b111, 0x13, 0x19, 0x11b, 0x1100b] {
+            let field = GF2n::new(polynomial).unwrap();
+            for a in (0..field.order()).step_by(97) {
+                let a = field.elem(a).unwrap();
+                for b in (0..field.order()).step_by(89) {
+                    let b = field.elem(b).unwrap();
+                    let product = mul_slow(field.value(a), field.value(b), polynomial);
+                    assert_eq!(field.value(field.mul(a, b)), product);
+                }
+                if a != field.zero() {
+                    assert_eq!(field.mul(a, field.inv(a).unwrap()), field.one());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn slices() {
+        // with the table of the products and with the logarithms
+        for polynomial in [0b111, 0x11b, 0x1100b] {
+            let field = GF2n::new(polynomial).unwrap();
+            let row: Vec<Number> = (0..200)
+                .map(|i| field.elem(i * 331 % field.order()).unwrap())
+                .collect();
+            for c in [0, 1, 2, field.order() - 1] {
+                let c = field.elem(c).unwrap();
+                let product: Vec<Number> = row.iter().map(|&x| field.mul(c, x)).collect();
+
+                let mut scaled = row.clone();
+                field.scale_slice(&mut scaled, c);
+                assert_eq!(scaled, product);
+
+                let mut sum = row.clone();
+                field.mul_add_slice(&mut sum, c, &row);
+                let expected: Vec<Number> = row
+                    .iter()
+                    .zip(&product)
+                    .map(|(&x, &y)| field.add(x, y))
+                    .collect();
+                assert_eq!(sum, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn small_field() {
+        // GF(2^4) / x^4 + x + 1
+        let field = GF2n::new(0x13).unwrap();
+        let a = field.elem(0b1001).unwrap();
+
+        assert_eq!(field.degree(), 4);
+        assert_eq!(field.mul(a, field.elem(0b0010).unwrap()), field.one());
+        assert_eq!(field.elem(16), None);
+        assert_ne!(field, GF2n::aes());
+    }
+
+    #[test]
+    fn invalid() {
+        for polynomial in [0, 1, 0x11, 0b10101, 0x100, 0x2000b] {
+            assert!(GF2n::new(polynomial).is_err(), "{:#x}", polynomial);
+        }
+    }
+
+    #[test]
+    fn irreducible() {
+        assert!(is_irreducible(0x11b));
+        assert!(is_irreducible(0x13));
+        assert!(is_irreducible(0b111));
+        assert!(is_irreducible(0b10));
+        // (x^2 + x + 1)^2, x^4 + 1 = (x + 1)^4
+        assert!(!is_irreducible(0b10101));
+        assert!(!is_irreducible(0x11));
+        assert!(!is_irreducible(0x100));
+        assert!(!is_irreducible(1));
+    }
+}
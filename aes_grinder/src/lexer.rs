@@ -0,0 +1,254 @@
+use log::trace;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Ident,    // W_0, S, const
+    Number,   // 02, 0x1b, 0b11, every literal starting with a digit
+    LBracket, // [
+    RBracket, // ]
+    Comma,    // ,
+    LParen,   // (
+    RParen,   // )
+    Plus,     // +
+    Star,     // *
+    Equal,    // =
+    Minus,    // -
+    Slash,    // /
+    Percent,  // %
+    Colon,    // :
+    Range,    // ..
+    Header,   // -- name: args, up to the end of line
+    Comment,  // #comment# or #comment up to the end of line
+    Newline,
+    Invalid, // any other character
+    Eof,
+}
+
+/// Token of the input, span is the byte range in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: (usize, usize),
+}
+
+/// Split a system source in tokens, in a single pass over the bytes
+pub struct Lexer {
+    src: String,
+    pos: usize,
+    line_starts: Vec<usize>, // byte offset of each line read so far
+}
+
+impl Lexer {
+    pub fn new(src: String) -> Self {
+        Lexer {
+            src,
+            pos: 0,
+            line_starts: vec![0],
+        }
+    }
+
+    /// Lexer starting at a byte offset of src, after a line start
+    pub fn new_at(src: String, offset: usize) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            src[..offset]
+                .bytes()
+                .enumerate()
+                .filter(|&(_, c)| c == b'\n')
+                .map(|(i, _)| i + 1),
+        );
+        Lexer {
+            src,
+            pos: offset,
+            line_starts,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.src
+    }
+
+    /// Text of a token
+    pub fn text(&self, token: &Token) -> &str {
+        &self.src[token.span.0..token.span.1]
+    }
+
+    /// Line and char (both from 1) of a byte offset already read
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let start = self.line_starts[line - 1];
+        (line, self.src[start..offset].chars().count() + 1)
+    }
+
+    /// Byte offset of the next character to read
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    pub fn next_token(&mut self) -> Token {
+        let bytes = self.src.as_bytes();
+
+        // Blanks only separate tokens
+        while self.pos < bytes.len()
+            && bytes[self.pos] != b'\n'
+            && bytes[self.pos].is_ascii_whitespace()
+        {
+            self.pos += 1;
+        }
+
+        let start = self.pos;
+        let Some(&c) = bytes.get(start) else {
+            return Token {
+                kind: TokenKind::Eof,
+                span: (start, start),
+            };
+        };
+
+        let kind = match c {
+            b'\n' => {
+                self.pos += 1;
+                self.line_starts.push(self.pos);
+                TokenKind::Newline
+            }
+            b'[' | b']' | b',' | b'(' | b')' | b'+' | b'*' | b'=' | b'/' | b'%' | b':' => {
+                self.pos += 1;
+                match c {
+                    b'[' => TokenKind::LBracket,
+                    b']' => TokenKind::RBracket,
+                    b',' => TokenKind::Comma,
+                    b'(' => TokenKind::LParen,
+                    b')' => TokenKind::RParen,
+                    b'+' => TokenKind::Plus,
+                    b'*' => TokenKind::Star,
+                    b'/' => TokenKind::Slash,
+                    b'%' => TokenKind::Percent,
+                    b':' => TokenKind::Colon,
+                    _ => TokenKind::Equal,
+                }
+            }
+            b'#' => {
+                // closed by a second # or by the end of line
+                self.pos += 1;
+                while self.pos < bytes.len() && bytes[self.pos] != b'\n' {
+                    self.pos += 1;
+                    if bytes[self.pos - 1] == b'#' {
+                        break;
+                    }
+                }
+                TokenKind::Comment
+            }
+            b'-' if bytes.get(start + 1) == Some(&b'-') => {
+                while self.pos < bytes.len() && bytes[self.pos] != b'\n' {
+                    self.pos += 1;
+                }
+                TokenKind::Header
+            }
+            b'-' => {
+                self.pos += 1;
+                TokenKind::Minus
+            }
+            b'.' if bytes.get(start + 1) == Some(&b'.') => {
+                self.pos += 2;
+                TokenKind::Range
+            }
+            c if c.is_ascii_digit() => {
+                self.eat_word();
+                TokenKind::Number
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                self.eat_word();
+                TokenKind::Ident
+            }
+            _ => {
+                self.pos += self.src[start..].chars().next().map_or(1, char::len_utf8);
+                TokenKind::Invalid
+            }
+        };
+
+        let token = Token {
+            kind,
+            span: (start, self.pos),
+        };
+        trace!(target: "parser", "token {:?} '{}'", token.kind, self.text(&token));
+        token
+    }
+
+    fn eat_word(&mut self) {
+        let bytes = self.src.as_bytes();
+        while self.pos < bytes.len()
+            && (bytes[self.pos].is_ascii_alphanumeric() || bytes[self.pos] == b'_')
+        {
+            self.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(src: &str) -> Vec<TokenKind> {
+        let mut lexer = Lexer::new(src.to_string());
+        let mut kinds = vec![];
+        loop {
+            let token = lexer.next_token();
+            kinds.push(token.kind);
+            if token.kind == TokenKind::Eof {
+                return kinds;
+            }
+        }
+    }
+
+    #[test]
+    fn term() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("02*S(X_0[0,1]) + c0 = 0x1b"),
+            [
+                Number, Star, Ident, LParen, Ident, LBracket, Number, Comma, Number, RBracket,
+                RParen, Plus, Ident, Equal, Number, Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn header_and_comments() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("-- system: a + b\na #in# + b # end\n- @"),
+            [Header, Newline, Ident, Comment, Plus, Ident, Comment, Newline, Minus, Invalid, Eof]
+        );
+    }
+
+    #[test]
+    fn loop_header() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("for i in 0..4: X[(i-1)%4, i/2]."),
+            [
+                Ident, Ident, Ident, Number, Range, Number, Colon, Ident, LBracket, LParen, Ident,
+                Minus, Number, RParen, Percent, Number, Comma, Ident, Slash, Number, RBracket,
+                Invalid, Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn spans_and_positions() {
+        let mut lexer = Lexer::new(String::from("a\n  é 0xg\n"));
+        lexer.next_token();
+        lexer.next_token();
+        let invalid = lexer.next_token();
+        let number = lexer.next_token();
+
+        assert_eq!(invalid.span, (4, 6));
+        assert_eq!(lexer.text(&number), "0xg");
+        assert_eq!(lexer.position(number.span.0), (2, 5));
+        assert_eq!(lexer.position(lexer.offset()), (2, 8));
+
+        let mut lexer = Lexer::new_at(String::from("a\nb\n  c"), 4);
+        let c = lexer.next_token();
+        assert_eq!(lexer.text(&c), "c");
+        assert_eq!(lexer.position(c.span.0), (3, 3));
+    }
+}
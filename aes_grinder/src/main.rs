@@ -1,22 +1,30 @@
 mod algo;
 mod cli;
+mod diff;
 mod exhaustive_search;
+mod field;
+mod lexer;
 mod matrix;
 mod parser;
+mod sbox;
 mod utils;
+mod var_table;
+mod variable;
+mod writer;
 
 use std::fs::read_dir;
 
-use crate::cli::Cli;
+use crate::cli::{Cli, Command};
 use clap::Parser as ClapParser;
 use dialoguer::FuzzySelect;
 use exhaustive_search::{exhaustive_search, random_search, Search};
+use matrix::Matrix;
 use strum::IntoEnumIterator;
 
 struct GlobalInfos {
     filename_eq_sys: String,
     sys_name: String,
-    polynomial: u16,
+    polynomial: u32,
 }
 
 impl GlobalInfos {
@@ -31,7 +39,20 @@ impl GlobalInfos {
 
 fn main() {
     // Sets the verbosity flag
-    Cli::parse();
+    let cli = Cli::parse();
+
+    if let Some(Command::Format {
+        files,
+        group_by_round,
+    }) = &cli.command
+    {
+        format_files(files, *group_by_round);
+        return;
+    }
+    if let Some(Command::Diff { left, right }) = &cli.command {
+        diff_files(left, right);
+        return;
+    }
 
     let selection = FuzzySelect::new()
         .with_prompt("What type of search ?")
@@ -68,30 +89,133 @@ fn main() {
     let system: &str = files.get(selection).unwrap();
 
     let mut globals: GlobalInfos = GlobalInfos::new(system.to_owned());
-    let mut parser_mod = parser::Parser::new(&globals);
+    let mut parser_mod = match parser::Parser::new(&globals) {
+        Ok(parser_mod) => parser_mod,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    let parsed = if cli.all_sections || cli.section.is_some() {
+        if cli.all_errors {
+            parser_mod.parse_sections_diagnostics(&mut globals)
+        } else {
+            parser_mod.parse_sections(&mut globals).map_err(|e| vec![e])
+        }
+    } else {
+        // Only the first section
+        if cli.all_errors {
+            parser_mod.parse_system_diagnostics(&mut globals)
+        } else {
+            parser_mod.parse_system(&mut globals).map_err(|e| vec![e])
+        }
+        .map(|matrix| vec![(globals.sys_name.clone(), matrix)])
+    };
+    for warning in parser_mod.take_warnings() {
+        eprintln!("{}", warning);
+    }
+    let mut sections = match parsed {
+        Ok(sections) => sections,
+        Err(errors) => {
+            for error in errors {
+                eprintln!("{}", error);
+            }
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(name) = &cli.section {
+        sections.retain(|(sys_name, _)| sys_name == name);
+        if sections.is_empty() {
+            eprintln!("{}: no section named '{}'", system, name);
+            std::process::exit(1);
+        }
+    }
+    if !cli.all_sections {
+        sections.truncate(1);
+    }
+
+    for (sys_name, matrix) in sections.iter_mut() {
+        println!("-- {}:", sys_name);
+        process_system(&search, matrix);
+    }
 
-    let matrix = parser_mod
-        .parse_system(&mut globals)
-        .expect("Error while parsing system");
-    let mut matrix = matrix;
-    matrix.set_vars_map(parser_mod.vars_map);
+    if let Some(filename) = &cli.save_reduced {
+        if let Err(error) = std::fs::write(filename, writer::write_system(&sections, false)) {
+            eprintln!("{}: {}", filename, error);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Rewrite each file in the canonical form, the files with errors, comments
+/// or constructs the canonical form would expand are left untouched
+fn format_files(files: &[String], group_by_round: bool) {
+    let mut failed = false;
+    for filename in files {
+        if let Err(error) = writer::format_file(filename, group_by_round) {
+            eprintln!("{}", error);
+            failed = true;
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Print the differences between the first systems of the files
+fn diff_files(left: &str, right: &str) {
+    let parse = |filename: &str| {
+        let mut globals = GlobalInfos::new(filename.to_owned());
+        let parsed = parser::Parser::new(&globals).and_then(|mut parser_mod| {
+            let matrix = parser_mod.parse_system(&mut globals);
+            for warning in parser_mod.take_warnings() {
+                eprintln!("{}", warning);
+            }
+            matrix
+        });
+        parsed.unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(2);
+        })
+    };
+    let (left_matrix, right_matrix) = (parse(left), parse(right));
+    if left_matrix.field() != right_matrix.field() {
+        eprintln!(
+            "{} is in {} and {} in {}",
+            left,
+            left_matrix.field(),
+            right,
+            right_matrix.field()
+        );
+        std::process::exit(2);
+    }
+
+    let diff = diff::SystemDiff::new(&left_matrix, &right_matrix);
+    print!("{}", diff.to_eqs(left, right));
+    if !diff.is_empty() {
+        std::process::exit(1);
+    }
+}
 
+fn process_system(search: &Search, matrix: &mut Matrix) {
     println!("{}", matrix);
     matrix.drop_linear_variables();
     println!("{}", matrix);
 
     match search {
         Search::Exhaustive => {
-            exhaustive_search(&mut matrix, 25)
+            exhaustive_search(matrix, 25)
                 .iter()
                 .next()
                 .unwrap()
-                .to_dot_debug("/tmp/algo.dot", &matrix)
+                .to_dot_debug("/tmp/algo.dot", matrix)
                 .unwrap();
         }
         Search::Random => {
-            random_search(&mut matrix)
-                .to_dot_debug("/tmp/algo.dot", &matrix)
+            random_search(matrix)
+                .to_dot_debug("/tmp/algo.dot", matrix)
                 .unwrap();
         }
     }
@@ -1,30 +1,137 @@
-use crate::utils::{Invertible, Number};
+use crate::field::{Field, GF2n};
+use crate::sbox::Sbox;
+use crate::var_table::VarTable;
+use crate::variable::{Role, Variable};
+use log::debug;
 use std::{
     cmp::min,
     collections::{HashMap, HashSet},
     fmt::Display,
 };
 
+/// How far Matrix::eliminate goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reduction {
+    Echelon, // zeros under the pivots
+    Reduced, // zeros under and above the pivots
+}
+
+/// Result of Matrix::eliminate, the system has no solution or has
+/// order^free.len() of them when rank < rows or columns are free
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Elimination {
+    pub rank: usize,
+    pub pivots: Vec<usize>, // Column of the pivot of each of the rank first lines
+    pub free: Vec<usize>,   // Eliminated columns without pivot
+    pub permutation: Vec<usize>, // Line of the matrix before the elimination of each line
+}
+
+/// Solutions of a system, the free variables take any value and give
+/// the value of the pivot variables
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneralSolution<F: Field = GF2n> {
+    field: F,
+    pub free: Vec<Variable>,
+    pub pivots: Vec<PivotVariable<F>>,
+}
+
+/// variable = constant + sum of coefficient * free variable
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PivotVariable<F: Field = GF2n> {
+    pub variable: Variable,
+    pub constant: F::Elem,
+    pub terms: Vec<(Variable, F::Elem)>,
+}
+
+#[allow(dead_code)]
+impl<F: Field> GeneralSolution<F> {
+    /// Solution where the free variables take the values, in their order
+    pub fn values(&self, free_values: &[F::Elem]) -> HashMap<Variable, F::Elem> {
+        assert_eq!(
+            free_values.len(),
+            self.free.len(),
+            "ERROR :: in values :: need one value per free variable"
+        );
+        let mut values: HashMap<Variable, F::Elem> = self
+            .free
+            .iter()
+            .cloned()
+            .zip(free_values.iter().copied())
+            .collect();
+        for pivot in &self.pivots {
+            let value = pivot.terms.iter().fold(pivot.constant, |sum, (var, coef)| {
+                self.field.add(sum, self.field.mul(*coef, values[var]))
+            });
+            values.insert(pivot.variable.clone(), value);
+        }
+        values
+    }
+}
+
+/// sum of coefficient * variable = rhs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equation<F: Field = GF2n> {
+    pub terms: Vec<(Variable, F::Elem)>,
+    pub rhs: F::Elem,
+}
+
+/// Verdict of Matrix::compare, the systems have the same solutions or
+/// one of them implies an equation the other does not
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Comparison<F: Field = GF2n> {
+    Equivalent,
+    OnlyLeft(Equation<F>),  // Implied by self, not by other
+    OnlyRight(Equation<F>), // Implied by other, not by self
+}
+
+/// Equations the values contradict, a sum of them with some coefficients
+/// has no variable left but its rhs is not 0, or the S(v) whose value is
+/// not the image of the value of v
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub equations: Vec<usize>,    // Lines of the matrix
+    pub variables: Vec<Variable>, // S(v) given with another value than S of v, sorted
+}
+
 #[derive(Debug, PartialEq, Clone)]
-pub struct Matrix {
-    vars_map: HashMap<String, usize>, // Map the variable name to the column index
+pub struct Matrix<F: Field = GF2n> {
+    vars_map: VarTable, // Column of each variable and variable of each column
     rows: usize,
     cols: usize,
-    data: Vec<Number>,
+    data: Vec<F::Elem>,
+    rhs: Vec<F::Elem>,              // Right hand side of each equation
+    roles: HashMap<Variable, Role>, // Declared roles, S(v) has the role of v
+    sboxes: HashMap<String, Sbox>,  // Table of each nonlinear function
+    field: F,                       // Field of the coefficients
 }
 
 impl Matrix {
     pub fn new(rows: usize, cols: usize) -> Self {
+        Matrix::with_field(GF2n::aes(), rows, cols)
+    }
+}
+
+impl<F: Field> Matrix<F> {
+    /// Matrix of zeros, its coefficients in field
+    pub fn with_field(field: F, rows: usize, cols: usize) -> Self {
         Matrix {
-            vars_map: HashMap::new(),
+            vars_map: VarTable::new(),
             rows,
             cols,
-            data: vec![0.into(); rows * cols],
+            data: vec![field.zero(); rows * cols],
+            rhs: vec![field.zero(); rows],
+            roles: HashMap::new(),
+            sboxes: HashMap::new(),
+            field,
         }
     }
 
     /// put argument vars to left of matrix
-    fn sort_left(&mut self, vars: Vec<String>) {
+    fn sort_left(&mut self, vars: Vec<Variable>) {
         let mut swap_ndx: usize = 0;
         let vars_iter = vars.iter();
         vars_iter.for_each(|var| {
@@ -37,7 +144,7 @@ impl Matrix {
     }
 
     /// put argument vars to right of matrix
-    fn sort_right(&mut self, vars: Vec<String>) {
+    fn sort_right(&mut self, vars: Vec<Variable>) {
         let mut swap_ndx: usize = self.cols - 1;
         let vars_iter = vars.iter();
 
@@ -52,24 +159,25 @@ impl Matrix {
 
     pub fn new_from_vec(
         data: Vec<Vec<u32>>,
-        _vars_map: HashMap<String, usize>,
-        polynomial: u16,
+        rhs: Vec<u32>,
+        vars_map: impl Into<VarTable>,
+        field: F,
     ) -> Self {
         let rows = data.len();
         let cols = data[0].len();
-        let mut matrix = Matrix::new(rows, cols);
+        let mut matrix = Matrix::with_field(field, rows, cols);
+        matrix.vars_map = vars_map.into();
         matrix.data.clear();
-
-        (0..rows).for_each(|i| {
-            for j in 0..cols {
-                if data[i][j] >= 2u32.pow(16 - polynomial.leading_zeros()) {
-                    panic!("Invalid number for the given polynomial");
-                }
-                matrix
-                    .data
-                    .push(Number::new(data[i][j].try_into().unwrap(), polynomial));
-            }
-        });
+        let elem = |value: u32| {
+            matrix
+                .field
+                .elem(value)
+                .unwrap_or_else(|| panic!("Invalid number for the given polynomial"))
+        };
+        let rhs = rhs.into_iter().map(elem).collect();
+        let data = data.into_iter().flatten().map(elem).collect();
+        matrix.rhs = rhs;
+        matrix.data = data;
 
         matrix
     }
@@ -79,7 +187,7 @@ impl Matrix {
     }
 
     // Give a Vec of the row
-    pub fn get_row(&self, row: usize) -> Vec<Number> {
+    pub fn get_row(&self, row: usize) -> Vec<F::Elem> {
         if row >= self.rows {
             panic!("Row index out of bounds");
         }
@@ -91,8 +199,17 @@ impl Matrix {
         r
     }
 
+    // Give the right hand side of the row
+    pub fn get_rhs(&self, row: usize) -> F::Elem {
+        if row >= self.rows {
+            panic!("Row index out of bounds");
+        }
+
+        self.rhs[row]
+    }
+
     // Give a Vec of the column
-    pub fn get_column(&self, column: usize) -> Vec<Number> {
+    pub fn get_column(&self, column: usize) -> Vec<F::Elem> {
         if column >= self.cols {
             panic!("Column index out of bounds");
         }
@@ -114,13 +231,7 @@ impl Matrix {
             self.data.swap(i * self.cols + col1, i * self.cols + col2);
         }
         //Swap in vars_map
-        for (_var, col) in self.vars_map.iter_mut() {
-            if *col == col1 {
-                *col = col2;
-            } else if *col == col2 {
-                *col = col1;
-            }
-        }
+        self.vars_map.swap(col1, col2);
     }
 
     pub fn delete_row(&mut self, row: usize) {
@@ -131,6 +242,7 @@ impl Matrix {
 
         // Remove the row in place
         self.data.drain(row * self.cols..(row + 1) * self.cols);
+        self.rhs.remove(row);
         self.rows -= 1;
     }
 
@@ -138,17 +250,9 @@ impl Matrix {
         if column >= self.cols {
             panic!("Column index out of bounds");
         }
-        println!(
-            "Deleted variable: {}",
-            self.vars_map.iter().find(|(_, v)| **v == column).unwrap().0
-        );
-        // update the vars_map
-        self.vars_map.retain(|_, v| *v != column);
-        // update the column index in the vars_map after column
-        for (_, v) in self.vars_map.iter_mut() {
-            if *v > column {
-                *v -= 1;
-            }
+        // update the vars_map, the columns after column move left
+        if let Some(var) = self.vars_map.remove_column(column) {
+            debug!("Deleted variable: {}", var);
         }
 
         // Remove the column
@@ -164,116 +268,75 @@ impl Matrix {
         self.cols -= 1;
     }
 
-    pub fn solve(&mut self) {
-        let mut pivot_line = 0;
-        for j in 0..self.cols {
-            if pivot_line >= self.rows {
-                break;
-            }
-            //Find the max
-            let mut max: Number = 0.into();
-            let mut max_row = 0;
-            for i in pivot_line..self.rows {
-                if self[(i, j)] > max {
-                    max = self[(i, j)];
-                    max_row = i;
-                }
-            }
-            if max == 0.into() {
-                panic!("ERROR :: in solve :: max == 0")
-            }
-
-            //Swap the pivot line to the right place
-            self.swap_lines(max_row, pivot_line);
-            let inverse = self[(pivot_line, j)].invert();
-            //Normalize the pivot line
-            for k in 0..self.cols {
-                self[(pivot_line, k)] = self[(pivot_line, k)] * inverse;
-            }
-
-            //Set 0 under the pivot
-            for k in pivot_line + 1..self.rows {
-                let factor = self[(k, j)];
-                for l in 0..self.cols {
-                    let a = self[(k, l)];
-                    let b = factor * self[(pivot_line, l)];
-                    let ab = a + b;
-                    self[(k, l)] = ab;
-                }
-            }
-            pivot_line += 1;
-        }
-        //Backward substitution
-        for j in (0..pivot_line).rev() {
-            for i in (0..j).rev() {
-                let factor = self[(i, j)];
-                for k in 0..self.cols {
-                    let a = self[(i, k)];
-                    let b = factor * self[(j, k)];
-                    let ab = a + b;
-                    self[(i, k)] = ab;
-                }
-            }
-        }
+    /// Reduced row echelon form, the pivots are 1
+    pub fn solve(&mut self) -> Elimination {
+        let columns: Vec<usize> = (0..self.cols).collect();
+        self.eliminate(&columns, Reduction::Reduced, true)
     }
 
-    /// Perform gaussian elimination with inversion on the given variables and return the number of echelon rows
-    pub fn solve_on(&mut self, vars: Vec<String>) {
+    /// Perform gaussian elimination with inversion on the given variables, put on the left
+    pub fn solve_on(&mut self, vars: Vec<Variable>) -> Elimination {
         assert!(
             vars.len() <= self.cols,
             "ERROR :: in solve_on :: vars.len() > self.cols"
         );
         self.sort_left(vars.clone());
-        let mut pivot_line = 0;
-        for j in 0..vars.len() {
-            if pivot_line >= self.rows {
-                break;
-            }
-            //Find the max
-            let mut max: Number = 0.into();
-            let mut max_row = 0;
-            for i in pivot_line..self.rows {
-                if self[(i, j)] > max {
-                    max = self[(i, j)];
-                    max_row = i;
-                }
-            }
-            if max == 0.into() {
-                panic!("ERROR :: in solve_on :: max == 0");
-            }
+        let columns: Vec<usize> = (0..vars.len()).collect();
+        self.eliminate(&columns, Reduction::Reduced, true)
+    }
+
+    /// Gaussian elimination on the columns, taken in their order
+    /// The pivot of a column is its greatest coefficient in the lines without
+    /// pivot, the line i ends with the pivot of the column pivots[i], and the
+    /// columns with only zeros there are free. Echelon clears the coefficients
+    /// under the pivots, Reduced above them too, normalize makes the pivots 1
+    pub fn eliminate(
+        &mut self,
+        columns: &[usize],
+        reduction: Reduction,
+        normalize: bool,
+    ) -> Elimination {
+        let zero = self.field.zero();
+        let mut elimination = Elimination {
+            rank: 0,
+            pivots: vec![],
+            free: vec![],
+            permutation: (0..self.rows).collect(),
+        };
+        for &j in columns {
+            let pivot_line = elimination.rank;
+            //Find the max, the first one of the lines
+            let max_row = (pivot_line..self.rows)
+                .rev()
+                .filter(|&i| self[(i, j)] != zero)
+                .max_by_key(|&i| self[(i, j)]);
+            let Some(max_row) = max_row else {
+                elimination.free.push(j);
+                continue;
+            };
 
             //Swap the pivot line to the right place
             self.swap_lines(max_row, pivot_line);
-            let inverse = self[(pivot_line, j)].invert();
-            //Normalize the pivot line
-            for k in 0..self.cols {
-                self[(pivot_line, k)] = self[(pivot_line, k)] * inverse;
+            elimination.permutation.swap(max_row, pivot_line);
+            let mut inverse = self.field.inv(self[(pivot_line, j)]).unwrap();
+            if normalize {
+                self.scale_line(pivot_line, inverse);
+                inverse = self.field.one();
             }
 
-            //Set 0 under the pivot
-            for k in pivot_line + 1..self.rows {
-                let factor = self[(k, j)];
-                for l in 0..self.cols {
-                    let a = self[(k, l)];
-                    let b = factor * self[(pivot_line, l)];
-                    let ab = a + b;
-                    self[(k, l)] = ab;
-                }
-            }
-            pivot_line += 1;
-        }
-        //Backward substitution
-        for j in (0..pivot_line).rev() {
-            for i in (0..j).rev() {
-                let factor = self[(i, j)];
-                for k in 0..self.cols {
-                    let a = self[(i, k)];
-                    let b = factor * self[(j, k)];
-                    let ab = a + b;
-                    self[(i, k)] = ab;
-                }
+            //Set 0 under the pivot, and above if reduced
+            let first = match reduction {
+                Reduction::Echelon => pivot_line + 1,
+                Reduction::Reduced => 0,
+            };
+            for k in (first..self.rows).filter(|&k| k != pivot_line) {
+                let factor = self.field.mul(self[(k, j)], inverse);
+                self.add_line(k, factor, pivot_line);
             }
+            elimination.rank += 1;
+            elimination.pivots.push(j);
         }
+        elimination
     }
 
     fn swap_lines(&mut self, i: usize, j: usize) {
@@ -290,100 +353,46 @@ impl Matrix {
         if i == j {
             return;
         }
-        for k in 0..self.cols {
-            let temp = self[(i, k)];
-            self[(i, k)] = self[(j, k)];
-            self[(j, k)] = temp;
-        }
+        let (line_i, line_j) = lines_mut(&mut self.data, self.cols, i, j);
+        line_i.swap_with_slice(line_j);
+        self.rhs.swap(i, j);
     }
 
-    /// Perform row reduction to get row echelon form
-    pub fn scale(&mut self) {
-        let mut pivot_line = 0;
-        for j in 0..self.cols {
-            if pivot_line >= self.rows {
-                break;
-            }
-            //Find the max
-            let mut max: Number = 0.into();
-            let mut max_row = 0;
-            for i in pivot_line..self.rows {
-                if self[(i, j)] > max {
-                    max = self[(i, j)];
-                    max_row = i;
-                }
-            }
-            if max == 0.into() {
-                continue;
-            }
-
-            //Swap the pivot line to the right place
-            self.swap_lines(max_row, pivot_line);
-            let inverse = self[(pivot_line, j)].invert();
-            //Normalize the pivot line
-            for k in 0..self.cols {
-                self[(pivot_line, k)] = self[(pivot_line, k)] * inverse;
-            }
+    /// line i = factor * line i, rhs included
+    fn scale_line(&mut self, i: usize, factor: F::Elem) {
+        let cols = self.cols;
+        self.field
+            .scale_slice(&mut self.data[i * cols..(i + 1) * cols], factor);
+        self.rhs[i] = self.field.mul(factor, self.rhs[i]);
+    }
 
-            //Set 0 under the pivot
-            for k in pivot_line + 1..self.rows {
-                let factor = self[(k, j)];
-                for l in 0..self.cols {
-                    let a = self[(k, l)];
-                    let b = factor * self[(pivot_line, l)];
-                    let ab = a + b;
-                    self[(k, l)] = ab;
-                }
-            }
-            pivot_line += 1;
+    /// line i = line i + factor * line j, rhs included
+    fn add_line(&mut self, i: usize, factor: F::Elem, j: usize) {
+        if factor == self.field.zero() {
+            return;
         }
+        let (line_i, line_j) = lines_mut(&mut self.data, self.cols, i, j);
+        self.field.mul_add_slice(line_i, factor, line_j);
+        self.rhs[i] = self
+            .field
+            .add(self.rhs[i], self.field.mul(factor, self.rhs[j]));
+    }
+
+    /// Perform row reduction to get row echelon form
+    pub fn scale(&mut self) -> Elimination {
+        let columns: Vec<usize> = (0..self.cols).collect();
+        self.eliminate(&columns, Reduction::Echelon, true)
     }
 
     /// Row reduce the matrix on the given variables
-    pub fn scale_on(&mut self, vars: Vec<String>) {
+    pub fn scale_on(&mut self, vars: Vec<Variable>) -> Elimination {
         assert!(
             vars.len() <= self.cols,
             "ERROR :: in scale_on :: vars.len() > self.cols"
         );
         self.sort_left(vars.clone());
-        let mut pivot_line = 0;
-        for j in 0..vars.len() {
-            if pivot_line >= self.rows {
-                break;
-            }
-            //Find the max
-            let mut max: Number = 0.into();
-            let mut max_row = 0;
-            for i in pivot_line..self.rows {
-                if self[(i, j)] > max {
-                    max = self[(i, j)];
-                    max_row = i;
-                }
-            }
-            if max == 0.into() {
-                continue;
-            }
-
-            //Swap the pivot line to the right place
-            self.swap_lines(max_row, pivot_line);
-            let inverse = self[(pivot_line, j)].invert();
-            //Normalize the pivot line
-            for k in 0..self.cols {
-                self[(pivot_line, k)] = self[(pivot_line, k)] * inverse;
-            }
-
-            //Set 0 under the pivot
-            for k in pivot_line + 1..self.rows {
-                let factor = self[(k, j)];
-                for l in 0..self.cols {
-                    let a = self[(k, l)];
-                    let b = factor * self[(pivot_line, l)];
-                    let ab = a + b;
-                    self[(k, l)] = ab;
-                }
-            }
-            pivot_line += 1;
-        }
+        let columns: Vec<usize> = (0..vars.len()).collect();
+        self.eliminate(&columns, Reduction::Echelon, true)
     }
 
     fn is_in_echelon_form(&self) -> bool {
@@ -391,7 +400,7 @@ impl Matrix {
         let mut only_zeros_allowed = false;
         for i in 0..self.rows {
             let row = self.get_row(i);
-            let index_first_non_zero = row.iter().position(|x| x.get_value() != 0);
+            let index_first_non_zero = row.iter().position(|x| *x != self.field.zero());
             match index_first_non_zero {
                 Some(first) => {
                     if only_zeros_allowed {
@@ -418,24 +427,17 @@ impl Matrix {
      * Compute the number of solution of the system of equations for the given variables
      * Compute |vars| - dim(M(vars))
      */
-    pub fn number_solutions(&mut self, vars: HashSet<String>) -> usize {
+    pub fn number_solutions(&mut self, vars: HashSet<Variable>) -> usize {
         //Echelonner matrice sur les non vars
         //Compter nombre d'equation en bas (0 sous non vars, en dessous matrice echellonée)
-        //Get variables from matrix that are not in vars
-        let not_vars: Vec<String> = self
+        //Get variables from matrix that are not in vars, S(v) goes with v
+        //Known variables are given, like vars
+        let not_vars: Vec<Variable> = self
             .get_all_variables()
-            .iter()
-            .filter(|v| {
-                let mut v = v.as_str();
-                if v.contains("S(") {
-                    //trim to get v such as S(v)
-                    v = &v[2..v.len() - 1];
-                }
-                !vars.contains(v)
-            })
-            .cloned()
+            .into_iter()
+            .filter(|v| !vars.contains(&v.input()) && !self.is_known(v))
             .collect();
-        self.scale_on(not_vars.clone());
+        let elimination = self.scale_on(not_vars.clone());
         let mat_by = self.get_matrix_generated_by(&not_vars);
 
         assert!(
@@ -447,38 +449,36 @@ impl Matrix {
             == self
                 .get_all_variables()
                 .iter()
-                .filter(|v| !v.contains("S("))
+                .filter(|v| !v.is_nonlinear() && !self.is_known(v))
                 .count()
         {
             return 0;
         }
 
-        let nb_eq = self.get_nb_ligne_zero_borded_from_bottom(vars.len());
-        vars.len() - nb_eq
-    }
-
-    /// From the bottom of the matrix, get the number of lines that are only made of 0
-    fn get_nb_ligne_zero_borded_from_bottom(&self, nb_vars: usize) -> usize {
-        assert!(nb_vars <= self.cols, "ERROR :: in get_nb_ligne_zero_borded_from_bottom :: nb_vars > self.cols \n vars : {}\n matrix:\n {}", nb_vars, self);
-        let max = self.cols - nb_vars;
-        let mut nb_ligne = 1;
-        for i in (0..self.rows - 1).rev() {
-            for j in 0..max {
-                if self[(i, j)] != 0.into() {
-                    return nb_ligne;
-                }
-            }
-            nb_ligne += 1;
-        }
-        nb_ligne
+        let nb_known = self
+            .get_all_variables()
+            .iter()
+            .filter(|v| !vars.contains(&v.input()) && self.is_known(v))
+            .count();
+        // The lines after the pivots only have vars and known variables
+        let nb_eq = self.rows - elimination.rank;
+        assert!(
+            nb_known + vars.len() <= self.cols,
+            "ERROR :: in number_solutions :: vars are not in the matrix"
+        );
+        vars.len().saturating_sub(nb_eq)
     }
 
-    pub fn get_matrix_generated_by(&self, vars: &Vec<String>) -> Matrix {
-        let mut matrix = Matrix::new(self.rows, vars.len());
+    pub fn get_matrix_generated_by(&self, vars: &Vec<Variable>) -> Matrix<F> {
+        let mut matrix = Matrix::with_field(self.field.clone(), self.rows, vars.len());
+        matrix.rhs.clone_from(&self.rhs);
+        matrix.roles.clone_from(&self.roles);
+        matrix.sboxes.clone_from(&self.sboxes);
         for (j, s) in vars.iter().enumerate() {
-            matrix.vars_map.insert(s.to_owned(), j);
+            let column = self.vars_map[s];
+            matrix.vars_map.insert(self.vars_map.shared(s).unwrap(), j);
             for i in 0..self.rows {
-                matrix[(i, j)] = self[(i, self.vars_map[s])];
+                matrix[(i, j)] = self[(i, column)];
             }
         }
         matrix
@@ -486,14 +486,69 @@ impl Matrix {
 
     /// Compute the dimension of the solution space of the system of equations
     fn dimension_solution_space(&mut self) -> usize {
-        self.scale();
-        let r = self.count_no_zero_rows();
-        println!(
-            "ECHEC :  non_zero:{r} col : {:?}, row:{}",
-            self.cols, self.rows
-        );
-        println!("MATRICE : \n{}", self);
-        self.cols - r as usize
+        self.scale().free.len()
+    }
+
+    /// Basis of the kernel, the solutions of the system with rhs 0,
+    /// one vector per free column, indexed by column
+    #[allow(dead_code)]
+    pub fn kernel(&self) -> Vec<Vec<F::Elem>> {
+        let mut matrix = self.clone();
+        let elimination = matrix.solve();
+        elimination
+            .free
+            .iter()
+            .map(|&f| {
+                let mut vector = vec![self.field.zero(); self.cols];
+                vector[f] = self.field.one();
+                // -a = a in characteristic 2
+                for (i, &p) in elimination.pivots.iter().enumerate() {
+                    vector[p] = matrix[(i, f)];
+                }
+                vector
+            })
+            .collect()
+    }
+
+    /// Every solution of the system, None if it has none
+    #[allow(dead_code)]
+    pub fn general_solution(&self) -> Option<GeneralSolution<F>> {
+        let mut matrix = self.clone();
+        let elimination = matrix.solve();
+        let zero = self.field.zero();
+        // 0 = rhs on the lines without pivot
+        if matrix.rhs[elimination.rank..].iter().any(|&b| b != zero) {
+            return None;
+        }
+
+        let variable = |j: usize| {
+            self.vars_map.variable(j).cloned().unwrap_or_else(|| {
+                panic!(
+                    "ERROR :: in general_solution :: no variable in column {}",
+                    j
+                )
+            })
+        };
+        let pivots = elimination
+            .pivots
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| PivotVariable {
+                variable: variable(p),
+                constant: matrix.rhs[i],
+                terms: elimination
+                    .free
+                    .iter()
+                    .filter(|&&f| matrix[(i, f)] != zero)
+                    .map(|&f| (variable(f), matrix[(i, f)]))
+                    .collect(),
+            })
+            .collect();
+        Some(GeneralSolution {
+            field: self.field.clone(),
+            free: elimination.free.iter().map(|&f| variable(f)).collect(),
+            pivots,
+        })
     }
 
     fn count_no_zero_rows(&self) -> u32 {
@@ -502,7 +557,7 @@ impl Matrix {
             let row = self.get_row(i);
             let mut is_zero = true;
             for num in row {
-                if num != 0.into() {
+                if num != self.field.zero() {
                     is_zero = false;
                     break;
                 }
@@ -514,15 +569,108 @@ impl Matrix {
         count
     }
 
-    pub fn are_valid_values(&self, _vars: &HashMap<String, u32>) -> bool {
-        //Check in the equations where the vars appears if the values are possible
-        todo!();
+    /// System left once the variables take the values, their terms go to
+    /// the rhs, S(v) takes the value of the table of S when v has a value
+    #[allow(dead_code)]
+    pub fn bind(&self, values: &HashMap<Variable, F::Elem>) -> Matrix<F> {
+        let mut values = values.clone();
+        for var in self.vars_map.keys() {
+            if values.contains_key(var) {
+                continue;
+            }
+            if let Some(y) = self.sbox_output(var, &values) {
+                values.insert(var.clone(), y);
+            }
+        }
+
+        let mut unbound: Vec<Variable> = self
+            .get_all_variables()
+            .into_iter()
+            .filter(|var| !values.contains_key(var))
+            .collect();
+        unbound.sort_by_key(|var| self.vars_map[var]);
+        let mut matrix = self.get_matrix_generated_by(&unbound);
+        for (var, &value) in &values {
+            if let Some(&j) = self.vars_map.get(var) {
+                for i in 0..self.rows {
+                    let term = self.field.mul(self[(i, j)], value);
+                    matrix.rhs[i] = self.field.add(matrix.rhs[i], term);
+                }
+            }
+        }
+        matrix
+    }
+
+    /// Value of S(v) given by the table of S and the value of v
+    fn sbox_output(&self, var: &Variable, values: &HashMap<Variable, F::Elem>) -> Option<F::Elem> {
+        let x = *values.get(&var.input())?;
+        let sbox = self.get_sbox(var.function()?)?;
+        let &y = sbox.table().get(self.field.value(x) as usize)?;
+        self.field.elem(y)
+    }
+
+    /// Solutions of the variables without value once the others take the
+    /// values, or the equations or the S(v) the values contradict
+    #[allow(dead_code)]
+    pub fn solve_with(
+        &self,
+        values: &HashMap<Variable, F::Elem>,
+    ) -> Result<GeneralSolution<F>, Conflict> {
+        // S(v) and v both given
+        let mut variables: Vec<Variable> = values
+            .iter()
+            .filter(|&(var, &y)| self.sbox_output(var, values).is_some_and(|s| s != y))
+            .map(|(var, _)| var.clone())
+            .collect();
+        if !variables.is_empty() {
+            variables.sort();
+            return Err(Conflict {
+                equations: vec![],
+                variables,
+            });
+        }
+
+        let bound = self.bind(values);
+        // An identity on the right keeps the sum of equations of each line
+        let cols = bound.cols;
+        let mut tracked = Matrix::with_field(self.field.clone(), self.rows, cols + self.rows);
+        tracked.rhs.clone_from(&bound.rhs);
+        for i in 0..self.rows {
+            for j in 0..cols {
+                tracked[(i, j)] = bound[(i, j)];
+            }
+            tracked[(i, cols + i)] = self.field.one();
+        }
+
+        let columns: Vec<usize> = (0..cols).collect();
+        let elimination = tracked.eliminate(&columns, Reduction::Echelon, false);
+        let zero = self.field.zero();
+        if let Some(i) = (elimination.rank..self.rows).find(|&i| tracked.rhs[i] != zero) {
+            return Err(Conflict {
+                equations: (0..self.rows)
+                    .filter(|&k| tracked[(i, cols + k)] != zero)
+                    .collect(),
+                variables: vec![],
+            });
+        }
+        Ok(bound
+            .general_solution()
+            .expect("ERROR :: in solve_with :: the system has a solution"))
+    }
+
+    /// Check in the equations where the vars appears if the values are possible
+    pub fn are_valid_values(&self, vars: &HashMap<Variable, u32>) -> bool {
+        let values: Option<HashMap<Variable, F::Elem>> = vars
+            .iter()
+            .map(|(var, &value)| Some((var.clone(), self.field.elem(value)?)))
+            .collect();
+        values.is_some_and(|values| self.solve_with(&values).is_ok())
     }
 
     pub fn is_only_one_1_on_column(&self, column: usize) -> bool {
         let mut count = 0;
         for i in 0..self.rows {
-            if self[(i, column)] == 1.into() {
+            if self[(i, column)] == self.field.one() {
                 count += 1;
             }
         }
@@ -530,22 +678,7 @@ impl Matrix {
     }
 
     pub fn rank(&mut self) -> usize {
-        self.scale();
-        let mut rank = 0;
-        for i in 0..self.rows {
-            let row = self.get_row(i);
-            let mut is_zero = true;
-            for num in row {
-                if num != 0.into() {
-                    is_zero = false;
-                    break;
-                }
-            }
-            if !is_zero {
-                rank += 1;
-            }
-        }
-        rank
+        self.scale().rank
     }
 
     ///Drop linear variable on the matrice, update the matrix self
@@ -596,19 +729,14 @@ impl Matrix {
     }
 
     fn delete_alone_variables(&mut self) {
-        let mut variables: Vec<String> = Vec::new();
-        self.vars_map.iter().for_each(|(name, _)| {
-            self.vars_map.iter().for_each(|(str, _)| {
-                if name.contains(str) && name != str {
-                    variables.push(str.to_string());
-                    variables.push(name.to_string());
-                }
-            });
-        });
+        let variables: Vec<Variable> = get_variable_if_sboxed(&self.get_all_variables())
+            .into_iter()
+            .flat_map(|(x, sx)| [x, sx])
+            .collect();
 
-        //Get all variables that doesnt appear under the sbox
-        let mut variables_alone: Vec<String> = self.get_all_variables();
-        variables_alone.retain(|s| !variables.contains(s));
+        //Get all variables that doesnt appear under the sbox, targets are kept
+        let mut variables_alone: Vec<Variable> = self.get_all_variables();
+        variables_alone.retain(|s| !variables.contains(s) && self.role(s) != Some(Role::Target));
         let debug = false;
         while !variables_alone.is_empty() {
             if debug {
@@ -620,7 +748,7 @@ impl Matrix {
                 println!("Variables selected {}", x);
             }
             //remove (scale and delete the row)
-            self.remove_variable(x.to_string());
+            self.remove_variable(x.clone());
             if debug {
                 println!("Matrix after removing {}\n{}", x, self);
             }
@@ -635,7 +763,12 @@ impl Matrix {
         let mut col_rank: Vec<usize> = Vec::new();
         for i in 0..self.cols {
             let c = self.get_column(i);
-            if c.iter().map(|number| number.get_value()).max().unwrap_or(0) <= r as u8 {
+            if c.iter()
+                .map(|&number| self.field.value(number))
+                .max()
+                .unwrap_or(0) as usize
+                <= r
+            {
                 col_rank.push(i);
             }
         }
@@ -643,15 +776,13 @@ impl Matrix {
     }
 
     ///Récupère les variables d'une colonne de rank max r
-    fn get_variable_of_max_rank(&self, r: usize) -> Vec<String> {
+    fn get_variable_of_max_rank(&self, r: usize) -> Vec<Variable> {
         let my_col = self.get_col_of_max_rank(r);
-        let mut variables: Vec<String> = Vec::new();
-        for (str, col) in &self.vars_map {
-            if my_col.contains(col) {
-                variables.push(str.to_string());
-            }
-        }
-        variables
+        my_col
+            .into_iter()
+            .filter_map(|col| self.vars_map.variable(col))
+            .cloned()
+            .collect()
     }
 
     /// Deletes all rows that are only made of 0 in place
@@ -661,7 +792,7 @@ impl Matrix {
             let row = self.get_row(last_update);
             let mut is_zero = true;
             for num in row {
-                if num.get_value() != 0 {
+                if num != self.field.zero() {
                     is_zero = false;
                     break;
                 }
@@ -681,7 +812,7 @@ impl Matrix {
             let column = self.get_column(last_update);
             let mut is_zero = true;
             for num in column {
-                if num.get_value() != 0 {
+                if num != self.field.zero() {
                     is_zero = false;
                     break;
                 }
@@ -695,14 +826,14 @@ impl Matrix {
     }
 
     ///Remove variables from string vec, update the matrix self
-    fn remove_variable(&mut self, variable: String) {
+    fn remove_variable(&mut self, variable: Variable) {
         if !self.vars_map.contains_key(&variable) {
             panic!("La Variable que l'on veut détruire n'existe pas");
         }
         self.solve_on(vec![variable.clone()]);
         //Remove first line and first column
         assert!(self.is_only_one_1_on_column(0), "ERROR :: in remove_variable :: we can only remove a variable if it is a combinaison of another variable");
-        if variable.contains('P') || variable.contains('C') || variable.contains("KV") {
+        if self.is_known(&variable) {
             self.delete_column(0);
         } else {
             self.delete_row(0);
@@ -711,10 +842,66 @@ impl Matrix {
     }
 
     ///Get all variable of the matrix
-    pub fn get_all_variables(&self) -> Vec<String> {
+    pub fn get_all_variables(&self) -> Vec<Variable> {
         self.vars_map.keys().cloned().collect()
     }
 
+    /// Declared role of a variable
+    pub fn role(&self, variable: &Variable) -> Option<Role> {
+        self.roles.get(&variable.input()).copied()
+    }
+
+    /// Declared role of a variable, else guessed from its name
+    pub fn role_or_guess(&self, variable: &Variable) -> Role {
+        self.role(variable)
+            .unwrap_or_else(|| Role::guess(&variable.input()))
+    }
+
+    /// True if the variable is known data, without declaration P and C are
+    pub fn is_known(&self, variable: &Variable) -> bool {
+        self.role_or_guess(variable).is_known()
+    }
+
+    /// Set the declared roles, keyed by the variables S is applied on
+    pub fn set_roles(&mut self, roles: HashMap<Variable, Role>) {
+        self.roles = roles;
+    }
+
+    /// Declared roles, keyed by the variables S is applied on
+    pub fn get_roles(&self) -> &HashMap<Variable, Role> {
+        &self.roles
+    }
+
+    /// Set the table of the nonlinear functions, keyed by name
+    pub fn set_sboxes(&mut self, sboxes: HashMap<String, Sbox>) {
+        self.sboxes = sboxes;
+    }
+
+    /// Table of the nonlinear function applied by name
+    pub fn get_sbox(&self, name: &str) -> Option<&Sbox> {
+        self.sboxes.get(name)
+    }
+
+    pub fn get_sboxes(&self) -> &HashMap<String, Sbox> {
+        &self.sboxes
+    }
+
+    /// Field of the coefficients
+    pub fn field(&self) -> &F {
+        &self.field
+    }
+
+    /// Variables to recover, the unknowns declared as target
+    pub fn get_target_variables(&self) -> Vec<Variable> {
+        let targets: HashSet<Variable> = self
+            .get_all_variables()
+            .into_iter()
+            .filter(|v| self.role(v) == Some(Role::Target))
+            .map(|v| v.input())
+            .collect();
+        targets.into_iter().collect()
+    }
+
     ///display variable names with their associated columns
     pub fn display_var_map(&self) {
         for (str, col) in &self.vars_map {
@@ -723,22 +910,31 @@ impl Matrix {
     }
 
     ///Set vars map (we need to use a fonction because parser issue)
-    pub fn set_vars_map(&mut self, vars_maps: HashMap<String, usize>) {
-        self.vars_map = vars_maps;
+    pub fn set_vars_map(&mut self, vars_maps: impl Into<VarTable>) {
+        self.vars_map = vars_maps.into();
+    }
+
+    pub fn get_vars_map(&self) -> &VarTable {
+        &self.vars_map
     }
 
     pub fn to_dot_string(&self) -> String {
         let mut res = String::new();
         res.push_str(&format!("Matrix ({}x{})\n", self.rows, self.cols));
         //Set width to the max length of the variable name
-        let max_len_word = self.vars_map.keys().map(|s| s.len()).max().unwrap_or(1);
+        let max_len_word = self
+            .vars_map
+            .keys()
+            .map(|s| s.to_string().len())
+            .max()
+            .unwrap_or(1);
 
         //Display var name above columns
         let vars_iter = self.vars_map.iter();
         //Put iter in a vec to sort it
         let mut vars_to_display: Vec<(String, usize)> = Vec::new();
         for var in vars_iter {
-            vars_to_display.push((var.0.clone(), *var.1));
+            vars_to_display.push((var.0.to_string(), *var.1));
         }
         //Sort names by column index
         vars_to_display.sort_by(|a, b| a.1.cmp(&b.1));
@@ -766,7 +962,7 @@ impl Matrix {
         // Print the matrix
         for i in 0..self.rows {
             for j in 0..self.cols {
-                let str_value = self[(i, j)].get_value().to_string();
+                let str_value = self.field.value(self[(i, j)]).to_string();
                 let padding = max_len_word - str_value.len();
                 if j == 0 {
                     res.push_str(&format!(
@@ -790,54 +986,156 @@ impl Matrix {
     }
 
     pub fn sort_columns(&mut self) {
-        let mut vars: Vec<String> = self.vars_map.keys().cloned().collect();
+        let mut vars: Vec<Variable> = self.vars_map.keys().cloned().collect();
         vars.sort();
         self.sort_left(vars);
     }
 
-    pub fn compare(&self, other: &Matrix) -> bool {
+    /// Compare the solutions of the systems whatever the order of their
+    /// lines and columns, a variable of only one system takes any value
+    /// in the other one
+    #[allow(dead_code)]
+    pub fn compare(&self, other: &Matrix<F>) -> Comparison<F> {
+        let vars = self.common_variables(other);
+
+        // Both in reduced row echelon form on the same columns
+        let ours = self.canonical_rows(&vars);
+        let theirs = other.canonical_rows(&vars);
+        if ours == theirs {
+            return Comparison::Equivalent;
+        }
+        let witness = |rows: &[Vec<F::Elem>], others: &[Vec<F::Elem>]| {
+            rows.iter()
+                .find(|row| !self.implies(others, row))
+                .map(|row| self.equation(&vars, row))
+        };
+        match (witness(&ours, &theirs), witness(&theirs, &ours)) {
+            (Some(equation), _) => Comparison::OnlyLeft(equation),
+            (None, Some(equation)) => Comparison::OnlyRight(equation),
+            // Both have no solution
+            (None, None) => Comparison::Equivalent,
+        }
+    }
+
+    /// Equations of the reduced row echelon form of the system, on the sorted
+    /// variables of both systems, that other does not imply. They do not
+    /// depend on how the lines are written. A variable of only one system
+    /// takes any value in the other
+    pub fn not_implied_by(&self, other: &Matrix<F>) -> Vec<Equation<F>> {
+        let vars = self.common_variables(other);
+        let theirs = other.canonical_rows(&vars);
+        self.canonical_rows(&vars)
+            .into_iter()
+            .filter(|row| !self.implies(&theirs, row))
+            .map(|row| self.equation(&vars, &row))
+            .collect()
+    }
+
+    /// Variables of both systems, sorted
+    fn common_variables(&self, other: &Matrix<F>) -> Vec<Variable> {
         assert!(
-            self.cols == other.cols,
-            "ERROR :: in compare :: self.cols != other.cols"
+            self.field == other.field,
+            "ERROR :: in common_variables :: self.field != other.field"
         );
-        assert!(
-            self.rows == other.rows,
-            "ERROR :: in compare :: self.rows != other.rows"
+        let mut vars = self.get_all_variables();
+        vars.extend(
+            other
+                .get_all_variables()
+                .into_iter()
+                .filter(|var| !self.vars_map.contains_key(var)),
         );
-        false
+        vars.sort();
+        vars
+    }
+
+    /// The system on the columns of vars, with the rhs as last column
+    fn rows_on(&self, vars: &[Variable]) -> Matrix<F> {
+        let n = vars.len();
+        let mut matrix = Matrix::with_field(self.field.clone(), self.rows, n + 1);
+        for (j, var) in vars.iter().enumerate() {
+            if let Some(&k) = self.vars_map.get(var) {
+                for i in 0..self.rows {
+                    matrix[(i, j)] = self[(i, k)];
+                }
+            }
+        }
+        for i in 0..self.rows {
+            matrix[(i, n)] = self.rhs[i];
+        }
+        matrix
+    }
+
+    /// Lines other than 0 of the reduced row echelon form of rows_on
+    fn canonical_rows(&self, vars: &[Variable]) -> Vec<Vec<F::Elem>> {
+        let mut matrix = self.rows_on(vars);
+        let rank = matrix.solve().rank;
+        (0..rank).map(|i| matrix.get_row(i)).collect()
+    }
+
+    /// True if the equation row, its rhs last, is a sum of the lines, or if
+    /// the lines have no solution, the lines being canonical_rows
+    fn implies(&self, rows: &[Vec<F::Elem>], row: &[F::Elem]) -> bool {
+        let zero = self.field.zero();
+        let n = row.len() - 1;
+        let mut row = row.to_vec();
+        for line in rows {
+            // The pivots are 1 with only zeros above and under them
+            match line[..n].iter().position(|&x| x != zero) {
+                Some(pivot) => {
+                    let factor = row[pivot];
+                    self.field.mul_add_slice(&mut row, factor, line);
+                }
+                // 0 = rhs
+                None => return true,
+            }
+        }
+        row.iter().all(|&x| x == zero)
+    }
+
+    /// Equation of a line on the columns of vars, its rhs last
+    fn equation(&self, vars: &[Variable], row: &[F::Elem]) -> Equation<F> {
+        Equation {
+            terms: vars
+                .iter()
+                .zip(row)
+                .filter(|(_, &coef)| coef != self.field.zero())
+                .map(|(var, &coef)| (var.clone(), coef))
+                .collect(),
+            rhs: row[vars.len()],
+        }
     }
 }
 
-impl Display for Matrix {
+impl<F: Field> Display for Matrix<F> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        // Print the vars_map in sorted order by values
-        let vars_map = self.vars_map.clone();
-        let mut vars_to_display: Vec<(String, usize)> = vars_map.into_iter().collect();
-        vars_to_display.sort_by(|a, b| a.1.cmp(&b.1));
-        for (str, col) in vars_to_display {
-            writeln!(f, "{} {}", str, col)?;
+        // Print the vars_map in the order of the columns
+        for col in 0..self.cols {
+            if let Some(var) = self.vars_map.variable(col) {
+                writeln!(f, "{} {}", var, col)?;
+            }
         }
 
         // Print the matrix
         for i in 0..self.rows {
             for j in 0..self.cols {
-                if self.data[i * self.cols + j] < 10.into() {
+                let value = self.field.value(self.data[i * self.cols + j]);
+                if value < 10 {
                     write!(f, "{}   ", self.data[i * self.cols + j])?;
-                } else if self.data[i * self.cols + j] < 100.into() {
+                } else if value < 100 {
                     write!(f, "{}  ", self.data[i * self.cols + j])?;
                 } else {
                     write!(f, "{} ", self.data[i * self.cols + j])?;
                 }
             }
-            writeln!(f)?;
+            writeln!(f, "| {}", self.rhs[i])?;
         }
         Ok(())
     }
 }
 
 /// Overload of []
-impl std::ops::Index<(usize, usize)> for Matrix {
-    type Output = Number;
+impl<F: Field> std::ops::Index<(usize, usize)> for Matrix<F> {
+    type Output = F::Elem;
 
     fn index(&self, index: (usize, usize)) -> &Self::Output {
         if index.0 >= self.rows || index.1 >= self.cols {
@@ -848,7 +1146,7 @@ impl std::ops::Index<(usize, usize)> for Matrix {
     }
 }
 
-impl std::ops::IndexMut<(usize, usize)> for Matrix {
+impl<F: Field> std::ops::IndexMut<(usize, usize)> for Matrix<F> {
     fn index_mut(&mut self, index: (usize, usize)) -> &mut Self::Output {
         if index.0 >= self.rows || index.1 >= self.cols {
             panic!("Index out of bounds");
@@ -866,7 +1164,9 @@ impl From<Vec<Vec<u8>>> for Matrix {
         matrix.data.clear();
         //Name the columns with alphabet
         for i in 0..cols {
-            matrix.vars_map.insert(format!("X_{}", i), i);
+            matrix
+                .vars_map
+                .insert(Variable::new(&format!("X_{}", i), vec![]), i);
         }
 
         (0..rows).for_each(|i| {
@@ -879,35 +1179,46 @@ impl From<Vec<Vec<u8>>> for Matrix {
     }
 }
 
-///retourne tout les variable S(x), x
-pub fn get_variable_if_sboxed(variables: &Vec<String>) -> Vec<(String, String)> {
-    let mut sboxed_variable: Vec<(String, String)> = vec![];
+/// Line i and line j, i != j, of the data of a matrix with cols columns
+fn lines_mut<T>(data: &mut [T], cols: usize, i: usize, j: usize) -> (&mut [T], &mut [T]) {
+    if i < j {
+        let (top, bottom) = data.split_at_mut(j * cols);
+        (&mut top[i * cols..(i + 1) * cols], &mut bottom[..cols])
+    } else {
+        let (top, bottom) = data.split_at_mut(i * cols);
+        (&mut bottom[..cols], &mut top[j * cols..(j + 1) * cols])
+    }
+}
+
+///retourne tout les couples (x, F(x)), un par fonction F appliquée sur x
+pub fn get_variable_if_sboxed(variables: &Vec<Variable>) -> Vec<(Variable, Variable)> {
+    let mut sboxed_variable: Vec<(Variable, Variable)> = vec![];
     for var in variables {
         for s in variables {
-            if s.contains(var) && !s.eq(var) {
-                sboxed_variable.push((var.to_string(), s.to_string()));
+            if s.is_nonlinear() && s.input() == *var {
+                sboxed_variable.push((var.clone(), s.clone()));
             }
         }
     }
     sboxed_variable
 }
 
-pub fn to_equations(matrix: &Matrix) -> Vec<String> {
+pub fn to_equations<F: Field>(matrix: &Matrix<F>) -> Vec<String> {
     let mut equations: Vec<String> = Vec::new();
     let mut matrix = matrix.clone();
     matrix.sort_columns();
     for i in 0..matrix.rows {
         let mut equation = String::new();
         for j in 0..matrix.cols {
-            if matrix[(i, j)] != 0.into() {
-                if matrix[(i, j)] != 1.into() {
+            if matrix[(i, j)] != matrix.field.zero() {
+                if matrix[(i, j)] != matrix.field.one() {
                     equation.push_str(&format!(
                         "{}*{}",
                         matrix[(i, j)],
-                        matrix.vars_map.iter().find(|(_, v)| **v == j).unwrap().0
+                        matrix.vars_map.variable(j).unwrap()
                     ));
                 } else {
-                    equation.push_str(&matrix.vars_map.iter().find(|(_, v)| **v == j).unwrap().0);
+                    equation.push_str(&matrix.vars_map.variable(j).unwrap().to_string());
                 }
                 equation.push_str(" + ");
             }
@@ -915,12 +1226,15 @@ pub fn to_equations(matrix: &Matrix) -> Vec<String> {
         equation.pop();
         equation.pop();
         equation.pop();
+        if matrix.rhs[i] != matrix.field.zero() {
+            equation.push_str(&format!(" = {}", matrix.rhs[i]));
+        }
         equations.push(equation);
     }
     equations
 }
 
-pub fn print_equations(matrix: &Matrix) {
+pub fn print_equations<F: Field>(matrix: &Matrix<F>) {
     let mut equations = to_equations(matrix);
     equations.sort();
     for equation in equations {
@@ -931,6 +1245,8 @@ pub fn print_equations(matrix: &Matrix) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::Number;
+    use crate::variable::var;
 
     #[test]
     fn test_index() {
@@ -985,7 +1301,7 @@ mod tests {
         matrix.delete_column(0);
         let mut expected = Matrix::from(vec![vec![2], vec![4]]);
         expected.vars_map.clear();
-        expected.vars_map.insert("X_1".to_string(), 0);
+        expected.vars_map.insert(var("X_1"), 0);
 
         assert_eq!(matrix, expected);
     }
@@ -1006,7 +1322,7 @@ mod tests {
         matrix.delete_empty_colums();
         let mut expected = Matrix::from(vec![vec![2], vec![4]]);
         expected.vars_map.clear();
-        expected.vars_map.insert("X_1".to_string(), 0);
+        expected.vars_map.insert(var("X_1"), 0);
         assert_eq!(matrix, expected);
     }
 
@@ -1015,12 +1331,12 @@ mod tests {
         let mut matrix = Matrix::new(2, 2);
         matrix[(0, 0)] = 1.into();
         matrix[(0, 1)] = 1.into();
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("X_0[0,0]".to_string(), 0);
-        vars_maps.insert("S(X_0[0,0])".to_string(), 1);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("X_0[0,0]"), 0);
+        vars_maps.insert(var("S(X_0[0,0])"), 1);
         matrix.set_vars_map(vars_maps);
 
-        let _expected = ["X_0[0,0]".to_string(), "S(X_0[0,0])".to_string()];
+        let _expected = [var("X_0[0,0]"), var("S(X_0[0,0])")];
         println!("{}", matrix);
     }
     #[test]
@@ -1033,9 +1349,9 @@ mod tests {
         println!("{}", matrix);
         matrix.solve();
         println!("{}", matrix);
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("X_0[0,0]".to_string(), 0);
-        vars_maps.insert("S(X_0[0,0])".to_string(), 1);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("X_0[0,0]"), 0);
+        vars_maps.insert(var("S(X_0[0,0])"), 1);
         matrix.set_vars_map(vars_maps);
     }
 
@@ -1064,17 +1380,13 @@ mod tests {
         matrix[(3, 2)] = 8.into();
         matrix[(3, 3)] = 1.into();
         matrix.set_vars_map(HashMap::from([
-            (String::from("x"), 0),
-            (String::from("y"), 1),
-            (String::from("z"), 2),
-            (String::from("k"), 3),
+            (var("x"), 0),
+            (var("y"), 1),
+            (var("z"), 2),
+            (var("k"), 3),
         ]));
         println!("{}", matrix);
-        matrix.scale_on(vec![
-            String::from("x"),
-            String::from("y"),
-            String::from("z"),
-        ]);
+        matrix.scale_on(vec![var("x"), var("y"), var("z")]);
         println!("{}", matrix);
     }
 
@@ -1099,51 +1411,50 @@ mod tests {
         matrix[(0, 0)] = 1.into();
         matrix[(1, 1)] = 1.into();
         matrix[(2, 2)] = 2.into();
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("W_0[0,0]".to_string(), 0);
-        vars_maps.insert("S(X_0[1,1])".to_string(), 1);
-        vars_maps.insert("X_0[1,1]".to_string(), 2);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("W_0[0,0]"), 0);
+        vars_maps.insert(var("S(X_0[1,1])"), 1);
+        vars_maps.insert(var("X_0[1,1]"), 2);
         matrix.set_vars_map(vars_maps);
 
         let mut test = matrix.get_variable_of_max_rank(0);
         test.sort();
-        let mut vec: Vec<String> = vec![];
+        let mut vec: Vec<Variable> = vec![];
         vec.sort();
         assert_eq!(test, vec);
         let mut test = matrix.get_variable_of_max_rank(1);
         test.sort();
-        let mut vec: Vec<String> = vec!["W_0[0,0]".to_string(), "S(X_0[1,1])".to_string()];
+        let mut vec: Vec<Variable> = vec![var("W_0[0,0]"), var("S(X_0[1,1])")];
         vec.sort();
         assert_eq!(test, vec);
         let mut test = matrix.get_variable_of_max_rank(2);
         test.sort();
-        let mut vec: Vec<String> = vec![
-            "W_0[0,0]".to_string(),
-            "S(X_0[1,1])".to_string(),
-            "X_0[1,1]".to_string(),
-        ];
+        let mut vec: Vec<Variable> = vec![var("W_0[0,0]"), var("S(X_0[1,1])"), var("X_0[1,1]")];
         vec.sort();
         assert_eq!(test, vec);
         let mut test = matrix.get_variable_of_max_rank(3);
         test.sort();
-        let mut vec: Vec<String> = vec![
-            "W_0[0,0]".to_string(),
-            "S(X_0[1,1])".to_string(),
-            "X_0[1,1]".to_string(),
-        ];
+        let mut vec: Vec<Variable> = vec![var("W_0[0,0]"), var("S(X_0[1,1])"), var("X_0[1,1]")];
         vec.sort();
         assert_eq!(test, vec);
     }
 
     #[test]
     fn test_get_variable_if_sboxed() {
-        let s: Vec<String> = vec!["X".to_string(), "S(X)".to_string(), "Y".to_string()];
+        let s: Vec<Variable> = vec![var("X"), var("S(X)"), var("Y")];
         let sboxed = get_variable_if_sboxed(&s);
-        let expect = vec![("X".to_string(), "S(X)".to_string())];
+        let expect = vec![(var("X"), var("S(X)"))];
         print!("{:?}", s);
         print!("{:?}", expect);
         assert_eq!(sboxed, expect);
     }
+    #[test]
+    fn test_get_variable_if_sboxed_exact() {
+        let s: Vec<Variable> = vec![var("X_1"), var("X_10"), var("S(X_10)"), var("KX_1")];
+        let sboxed = get_variable_if_sboxed(&s);
+        assert_eq!(sboxed, vec![(var("X_10"), var("S(X_10)"))]);
+    }
+
     #[test]
     fn test_count_no_zero_rows() {
         let mut matrix = Matrix::new(3, 3);
@@ -1177,15 +1488,15 @@ mod tests {
         matrix[(0, 0)] = 1.into();
         matrix[(1, 1)] = 1.into();
         matrix[(2, 2)] = 2.into();
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("W_0[0,0]".to_string(), 0);
-        vars_maps.insert("S(X_0[1,1])".to_string(), 1);
-        vars_maps.insert("X_0[1,1]".to_string(), 2);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("W_0[0,0]"), 0);
+        vars_maps.insert(var("S(X_0[1,1])"), 1);
+        vars_maps.insert(var("X_0[1,1]"), 2);
         matrix.set_vars_map(vars_maps);
 
         matrix.delete_alone_variables();
         let mut m = matrix.get_all_variables();
-        let mut expect = vec!["S(X_0[1,1])".to_string(), "X_0[1,1]".to_string()];
+        let mut expect = vec![var("S(X_0[1,1])"), var("X_0[1,1]")];
         m.sort();
         expect.sort();
         println!("variable de matrix = {:?}", m);
@@ -1194,15 +1505,260 @@ mod tests {
         assert_eq!(m, expect);
     }
 
+    #[test]
+    fn test_delete_alone_variable_target() {
+        let mut matrix = Matrix::new(3, 3);
+        matrix[(0, 0)] = 1.into();
+        matrix[(1, 1)] = 1.into();
+        matrix[(2, 2)] = 2.into();
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("W_0[0,0]"), 0);
+        vars_maps.insert(var("S(X_0[1,1])"), 1);
+        vars_maps.insert(var("X_0[1,1]"), 2);
+        matrix.set_vars_map(vars_maps);
+        matrix.set_roles(HashMap::from([(var("W_0[0,0]"), Role::Target)]));
+
+        matrix.delete_alone_variables();
+
+        assert_eq!(matrix.get_all_variables().len(), 3);
+        assert_eq!(matrix.get_target_variables(), [var("W_0[0,0]")]);
+    }
+
+    #[test]
+    fn test_remove_variable_role() {
+        let build = || {
+            let mut matrix = Matrix::from(vec![vec![1, 1], vec![0, 1]]);
+            matrix.set_vars_map(HashMap::from([(var("A"), 0), (var("B"), 1)]));
+            matrix
+        };
+
+        // unknown, its equation is dropped
+        let mut matrix = build();
+        matrix.remove_variable(var("A"));
+        assert_eq!((matrix.rows, matrix.cols), (1, 1));
+
+        // known, only its column is dropped
+        let mut matrix = build();
+        matrix.set_roles(HashMap::from([(var("A"), Role::Plaintext)]));
+        assert!(matrix.is_known(&var("A")) && !matrix.is_known(&var("B")));
+        assert!(matrix.is_known(&var("S(C[0,0])")) && !matrix.is_known(&var("K[0,0]")));
+        matrix.remove_variable(var("A"));
+        assert_eq!((matrix.rows, matrix.cols), (2, 1));
+    }
+
+    #[test]
+    fn test_general_solution() {
+        // A + B + C = 1, B + 02*C = 3
+        let vars_map = HashMap::from([(var("A"), 0), (var("B"), 1), (var("C"), 2)]);
+        let matrix = Matrix::new_from_vec(
+            vec![vec![1, 1, 1], vec![0, 1, 2]],
+            vec![1, 3],
+            vars_map,
+            GF2n::aes(),
+        );
+        let n = |value: u8| Number::from(value);
+
+        assert_eq!(matrix.kernel(), [[n(3), n(2), n(1)]]);
+
+        let solution = matrix.general_solution().unwrap();
+        assert_eq!(solution.free, [var("C")]);
+        assert_eq!(
+            solution.pivots,
+            [
+                PivotVariable {
+                    variable: var("A"),
+                    constant: n(2),
+                    terms: vec![(var("C"), n(3))],
+                },
+                PivotVariable {
+                    variable: var("B"),
+                    constant: n(3),
+                    terms: vec![(var("C"), n(2))],
+                },
+            ]
+        );
+        let values = solution.values(&[n(5)]);
+        assert_eq!(
+            values,
+            HashMap::from([(var("A"), n(13)), (var("B"), n(9)), (var("C"), n(5))])
+        );
+    }
+
+    #[test]
+    fn test_general_solution_none() {
+        // A + B = 0, A + B = 1
+        let vars_map = HashMap::from([(var("A"), 0), (var("B"), 1)]);
+        let matrix = Matrix::new_from_vec(
+            vec![vec![1, 1], vec![1, 1]],
+            vec![0, 1],
+            vars_map,
+            GF2n::aes(),
+        );
+
+        assert_eq!(matrix.kernel(), [[Number::from(1), Number::from(1)]]);
+        assert_eq!(matrix.general_solution(), None);
+    }
+
+    #[test]
+    fn test_solve_with() {
+        // P + K + X = 0, X + Y = 1
+        let vars_map = HashMap::from([(var("P"), 0), (var("K"), 1), (var("X"), 2), (var("Y"), 3)]);
+        let matrix = Matrix::new_from_vec(
+            vec![vec![1, 1, 1, 0], vec![0, 0, 1, 1]],
+            vec![0, 1],
+            vars_map,
+            GF2n::aes(),
+        );
+        let n = |value: u8| Number::from(value);
+
+        let solution = matrix
+            .solve_with(&HashMap::from([(var("P"), n(3)), (var("K"), n(5))]))
+            .unwrap();
+        assert!(solution.free.is_empty());
+        assert_eq!(
+            solution.values(&[]),
+            HashMap::from([(var("X"), n(6)), (var("Y"), n(7))])
+        );
+
+        // only P, K is free
+        let solution = matrix
+            .solve_with(&HashMap::from([(var("P"), n(3))]))
+            .unwrap();
+        assert_eq!(solution.free, [var("Y")]);
+        assert_eq!(solution.values(&[n(7)])[&var("K")], n(5));
+    }
+
+    #[test]
+    fn test_solve_with_sbox() {
+        // X + S(X) + Y = 0
+        let vars_map = HashMap::from([(var("X"), 0), (var("S(X)"), 1), (var("Y"), 2)]);
+        let mut matrix = Matrix::new_from_vec(vec![vec![1, 1, 1]], vec![0], vars_map, GF2n::aes());
+        matrix.set_sboxes(HashMap::from([(String::from("S"), Sbox::aes())]));
+
+        let solution = matrix
+            .solve_with(&HashMap::from([(var("X"), Number::from(1))]))
+            .unwrap();
+        // S(1) = 0x7c
+        assert_eq!(solution.values(&[])[&var("Y")], Number::from(0x7d));
+
+        // S(X) is not S of X
+        let values = HashMap::from([(var("X"), Number::from(1)), (var("S(X)"), Number::from(1))]);
+        assert_eq!(
+            matrix.solve_with(&values),
+            Err(Conflict {
+                equations: vec![],
+                variables: vec![var("S(X)")]
+            })
+        );
+        assert!(!matrix.are_valid_values(&HashMap::from([(var("X"), 1), (var("S(X)"), 1)])));
+        assert!(matrix.are_valid_values(&HashMap::from([(var("X"), 1), (var("S(X)"), 0x7c)])));
+    }
+
+    #[test]
+    fn test_solve_with_conflict() {
+        // P + K = 0, K = 1, X + Y = 0
+        let vars_map = HashMap::from([(var("P"), 0), (var("K"), 1), (var("X"), 2), (var("Y"), 3)]);
+        let matrix = Matrix::new_from_vec(
+            vec![vec![1, 1, 0, 0], vec![0, 1, 0, 0], vec![0, 0, 1, 1]],
+            vec![0, 1, 0],
+            vars_map,
+            GF2n::aes(),
+        );
+
+        assert_eq!(
+            matrix.solve_with(&HashMap::from([(var("P"), Number::from(2))])),
+            Err(Conflict {
+                equations: vec![0, 1],
+                variables: vec![]
+            })
+        );
+        assert!(!matrix.are_valid_values(&HashMap::from([(var("P"), 2)])));
+        assert!(matrix.are_valid_values(&HashMap::from([(var("P"), 1)])));
+        assert!(matrix.are_valid_values(&HashMap::from([(var("X"), 4), (var("Y"), 4)])));
+        // not in GF(2^8)
+        assert!(!matrix.are_valid_values(&HashMap::from([(var("P"), 256)])));
+    }
+
+    #[test]
+    fn test_compare() {
+        // A + B = 1, B + C = 2
+        let matrix = Matrix::new_from_vec(
+            vec![vec![1, 1, 0], vec![0, 1, 1]],
+            vec![1, 2],
+            HashMap::from([(var("A"), 0), (var("B"), 1), (var("C"), 2)]),
+            GF2n::aes(),
+        );
+        // 02*B + 02*C = 04, A + C = 3, in the order C, A, B
+        let vars_map = HashMap::from([(var("C"), 0), (var("A"), 1), (var("B"), 2)]);
+        let same = Matrix::new_from_vec(
+            vec![vec![2, 0, 2], vec![1, 1, 0]],
+            vec![4, 3],
+            vars_map.clone(),
+            GF2n::aes(),
+        );
+        // A + B = 1, B + C = 3
+        let other = Matrix::new_from_vec(
+            vec![vec![0, 1, 1], vec![1, 0, 1]],
+            vec![1, 3],
+            vars_map,
+            GF2n::aes(),
+        );
+        let n = |value: u8| Number::from(value);
+
+        assert_eq!(matrix.compare(&same), Comparison::Equivalent);
+        assert_eq!(same.compare(&matrix), Comparison::Equivalent);
+        assert_eq!(
+            matrix.compare(&other),
+            Comparison::OnlyLeft(Equation {
+                terms: vec![(var("A"), n(1)), (var("C"), n(1))],
+                rhs: n(3),
+            })
+        );
+    }
+
+    #[test]
+    fn test_compare_variables() {
+        // A = 1, against A = 1, D = 0
+        let matrix = Matrix::new_from_vec(
+            vec![vec![1]],
+            vec![1],
+            HashMap::from([(var("A"), 0)]),
+            GF2n::aes(),
+        );
+        let other = Matrix::new_from_vec(
+            vec![vec![1, 0], vec![0, 1]],
+            vec![1, 0],
+            HashMap::from([(var("A"), 0), (var("D"), 1)]),
+            GF2n::aes(),
+        );
+        // A = 1, A = 2, no solution as the others
+        let none = Matrix::new_from_vec(
+            vec![vec![1], vec![1]],
+            vec![1, 2],
+            HashMap::from([(var("A"), 0)]),
+            GF2n::aes(),
+        );
+
+        assert_eq!(
+            matrix.compare(&other),
+            Comparison::OnlyRight(Equation {
+                terms: vec![(var("D"), Number::from(1))],
+                rhs: Number::from(0),
+            })
+        );
+        assert_eq!(none.compare(&none.clone()), Comparison::Equivalent);
+        assert!(matches!(none.compare(&matrix), Comparison::OnlyLeft(_)));
+    }
+
     #[test]
     fn test_number_solutions() {
         //une solution
         let mut matrix = Matrix::from(vec![vec![1, 0], vec![0, 1]]);
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("A".to_string(), 0);
-        vars_maps.insert("B".to_string(), 1);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("A"), 0);
+        vars_maps.insert(var("B"), 1);
         matrix.set_vars_map(vars_maps);
-        let nb_sol = matrix.number_solutions(HashSet::<String>::from(["B".to_string()]));
+        let nb_sol = matrix.number_solutions(HashSet::<Variable>::from([var("B")]));
         assert_eq!(1, nb_sol);
     }
 }
@@ -1210,6 +1766,7 @@ mod tests {
 #[cfg(test)]
 mod test_fn_solve {
     use super::*;
+    use crate::variable::var;
 
     #[test]
     fn test_solve_00() {
@@ -1253,10 +1810,10 @@ mod test_fn_solve {
     #[test]
     fn test_solve_03() {
         let mut matrix = Matrix::from(vec![vec![4, 4, 111], vec![4, 21, 250], vec![7, 8, 9]]);
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("A".to_string(), 0);
-        vars_maps.insert("B".to_string(), 1);
-        vars_maps.insert("C".to_string(), 2);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("A"), 0);
+        vars_maps.insert(var("B"), 1);
+        vars_maps.insert(var("C"), 2);
         matrix.set_vars_map(vars_maps.clone());
         println!("matrice a : \n{}", matrix);
         let mut expected = Matrix::from(vec![vec![1, 0, 101], vec![0, 1, 56], vec![0, 0, 242]]);
@@ -1268,26 +1825,55 @@ mod test_fn_solve {
         println!("matrice obtenue : \n{}", matrix);
         assert_eq!(matrix, expected);
     }
+
+    #[test]
+    fn test_solve_rank_deficient() {
+        let mut matrix = Matrix::from(vec![vec![1, 1, 0], vec![2, 2, 0], vec![0, 0, 1]]);
+
+        let elimination = matrix.solve();
+
+        assert_eq!(elimination.rank, 2);
+        assert_eq!(elimination.pivots, [0, 2]);
+        assert_eq!(elimination.free, [1]);
+        assert_eq!(elimination.permutation, [1, 2, 0]);
+        assert_eq!(
+            matrix,
+            Matrix::from(vec![vec![1, 1, 0], vec![0, 0, 1], vec![0, 0, 0]])
+        );
+    }
+
+    #[test]
+    fn test_eliminate_not_normalized() {
+        let mut matrix = Matrix::from(vec![vec![2, 0], vec![3, 1]]);
+
+        let elimination = matrix.eliminate(&[0, 1], Reduction::Echelon, false);
+
+        // 2 + 2 * 3^-1 * 3 = 0 and 2 * 3^-1 * 1 = 0xf7
+        assert_eq!(elimination.rank, 2);
+        assert_eq!(elimination.permutation, [1, 0]);
+        assert_eq!(matrix, Matrix::from(vec![vec![3, 1], vec![0, 0xf7]]));
+    }
 }
 
 #[cfg(test)]
 mod test_fn_solve_on {
     use super::*;
+    use crate::variable::var;
 
     #[test]
     fn test_solve_on_00() {
         let mut matrix = Matrix::from(vec![vec![4, 4, 111], vec![4, 21, 250], vec![7, 8, 9]]);
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("A".to_string(), 0);
-        vars_maps.insert("B".to_string(), 1);
-        vars_maps.insert("C".to_string(), 2);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("A"), 0);
+        vars_maps.insert(var("B"), 1);
+        vars_maps.insert(var("C"), 2);
         matrix.set_vars_map(vars_maps.clone());
         println!("matrice a : \n{}", matrix);
         let mut expected = Matrix::from(vec![vec![1, 0, 101], vec![0, 1, 56], vec![0, 0, 242]]);
         expected.set_vars_map(vars_maps.clone());
 
-        matrix.solve_on(vec!["A".to_string(), "B".to_string()]);
-        matrix.solve_on(vec!["A".to_string(), "B".to_string()]);
+        matrix.solve_on(vec![var("A"), var("B")]);
+        matrix.solve_on(vec![var("A"), var("B")]);
         println!("matrice expected : \n{}", expected);
         println!("matrice obtenue : \n{}", matrix);
         assert_eq!(matrix, expected);
@@ -1296,17 +1882,17 @@ mod test_fn_solve_on {
     #[test]
     fn test_solve_on_01() {
         let mut matrix = Matrix::from(vec![vec![1, 2, 3, 4], vec![4, 3, 2, 1]]);
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("A".to_string(), 0);
-        vars_maps.insert("B".to_string(), 1);
-        vars_maps.insert("C".to_string(), 2);
-        vars_maps.insert("D".to_string(), 3);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("A"), 0);
+        vars_maps.insert(var("B"), 1);
+        vars_maps.insert(var("C"), 2);
+        vars_maps.insert(var("D"), 3);
         matrix.set_vars_map(vars_maps.clone());
 
         println!("matrice a : \n{}", matrix);
         //expected.set_vars_map(vars_maps.clone());
 
-        matrix.solve_on(vec!["A".to_string(), "B".to_string()]);
+        matrix.solve_on(vec![var("A"), var("B")]);
         //println!("matrice expected : \n{}", expected);
         println!("matrice obtenue : \n{}", matrix);
 
@@ -1323,16 +1909,16 @@ mod test_fn_solve_on {
     #[test]
     fn test_solve_on_02() {
         let mut matrix = Matrix::from(vec![vec![1, 2, 3, 4], vec![4, 3, 2, 1]]);
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("A".to_string(), 0);
-        vars_maps.insert("B".to_string(), 1);
-        vars_maps.insert("C".to_string(), 2);
-        vars_maps.insert("D".to_string(), 3);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("A"), 0);
+        vars_maps.insert(var("B"), 1);
+        vars_maps.insert(var("C"), 2);
+        vars_maps.insert(var("D"), 3);
         matrix.set_vars_map(vars_maps.clone());
 
         println!("matrice a : \n{}", matrix);
 
-        matrix.solve_on(vec!["A".to_string()]);
+        matrix.solve_on(vec![var("A")]);
         println!("matrice obtenue : \n{}", matrix);
 
         assert_eq!(
@@ -1350,6 +1936,7 @@ mod test_fn_solve_on {
 mod test_fn_swap {
 
     use super::*;
+    use crate::variable::var;
 
     #[test]
     fn swap_columns_00() {
@@ -1358,23 +1945,23 @@ mod test_fn_swap {
         matrix[(0, 1)] = 1.into();
         matrix[(0, 2)] = 2.into();
 
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("x".to_string(), 0);
-        vars_maps.insert("y".to_string(), 1);
-        vars_maps.insert("z".to_string(), 2);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("x"), 0);
+        vars_maps.insert(var("y"), 1);
+        vars_maps.insert(var("z"), 2);
         matrix.set_vars_map(vars_maps);
 
         matrix.swap_columns(0, 2);
 
-        assert_eq!(matrix.vars_map.get("x").unwrap(), &2);
-        assert_eq!(matrix.vars_map.get("z").unwrap(), &0);
+        assert_eq!(matrix.vars_map.get(&var("x")).unwrap(), &2);
+        assert_eq!(matrix.vars_map.get(&var("z")).unwrap(), &0);
         assert_eq!(matrix[(0, 0)], 2.into());
         assert_eq!(matrix[(0, 2)], 0.into());
 
         matrix.swap_columns(0, 1);
 
-        assert_eq!(matrix.vars_map.get("y").unwrap(), &0);
-        assert_eq!(matrix.vars_map.get("z").unwrap(), &1);
+        assert_eq!(matrix.vars_map.get(&var("y")).unwrap(), &0);
+        assert_eq!(matrix.vars_map.get(&var("z")).unwrap(), &1);
         assert_eq!(matrix[(0, 0)], 1.into());
         assert_eq!(matrix[(0, 1)], 2.into());
     }
@@ -1392,16 +1979,16 @@ mod test_fn_swap {
         matrix[(2, 1)] = 1.into();
         matrix[(2, 2)] = 2.into();
 
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("x".to_string(), 0);
-        vars_maps.insert("y".to_string(), 1);
-        vars_maps.insert("z".to_string(), 2);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("x"), 0);
+        vars_maps.insert(var("y"), 1);
+        vars_maps.insert(var("z"), 2);
         matrix.set_vars_map(vars_maps);
 
         matrix.swap_columns(0, 2);
 
-        assert_eq!(matrix.vars_map.get("x").unwrap(), &2);
-        assert_eq!(matrix.vars_map.get("z").unwrap(), &0);
+        assert_eq!(matrix.vars_map.get(&var("x")).unwrap(), &2);
+        assert_eq!(matrix.vars_map.get(&var("z")).unwrap(), &0);
         assert_eq!(matrix[(0, 0)], 2.into());
         assert_eq!(matrix[(1, 0)], 2.into());
         assert_eq!(matrix[(2, 0)], 2.into());
@@ -1411,8 +1998,8 @@ mod test_fn_swap {
 
         matrix.swap_columns(0, 1);
 
-        assert_eq!(matrix.vars_map.get("y").unwrap(), &0);
-        assert_eq!(matrix.vars_map.get("z").unwrap(), &1);
+        assert_eq!(matrix.vars_map.get(&var("y")).unwrap(), &0);
+        assert_eq!(matrix.vars_map.get(&var("z")).unwrap(), &1);
         assert_eq!(matrix[(0, 0)], 1.into());
         assert_eq!(matrix[(1, 0)], 1.into());
         assert_eq!(matrix[(2, 0)], 1.into());
@@ -1425,15 +2012,14 @@ mod test_fn_swap {
     fn test_number_solutions2() {
         let mut matrix = Matrix::from(vec![vec![1, 4, 1, 1], vec![0, 1, 1, 0], vec![0, 0, 0, 1]]);
 
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("A".to_string(), 0);
-        vars_maps.insert("B".to_string(), 1);
-        vars_maps.insert("C".to_string(), 2);
-        vars_maps.insert("D".to_string(), 3);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("A"), 0);
+        vars_maps.insert(var("B"), 1);
+        vars_maps.insert(var("C"), 2);
+        vars_maps.insert(var("D"), 3);
         matrix.set_vars_map(vars_maps);
         println!(" m : {}", matrix);
-        let nb_sol =
-            matrix.number_solutions(HashSet::<String>::from(["C".to_string(), "D".to_string()]));
+        let nb_sol = matrix.number_solutions(HashSet::<Variable>::from([var("C"), var("D")]));
         print!("sol : {}", nb_sol);
         println!(" m : {}", matrix);
         assert_eq!(1, nb_sol);
@@ -1447,15 +2033,14 @@ mod test_fn_swap {
             vec![0, 7, 0, 1],
         ]);
 
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("A".to_string(), 0);
-        vars_maps.insert("B".to_string(), 1);
-        vars_maps.insert("C".to_string(), 2);
-        vars_maps.insert("D".to_string(), 3);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("A"), 0);
+        vars_maps.insert(var("B"), 1);
+        vars_maps.insert(var("C"), 2);
+        vars_maps.insert(var("D"), 3);
         matrix.set_vars_map(vars_maps);
         println!(" m : {}", matrix);
-        let nb_sol =
-            matrix.number_solutions(HashSet::<String>::from(["C".to_string(), "D".to_string()]));
+        let nb_sol = matrix.number_solutions(HashSet::<Variable>::from([var("C"), var("D")]));
         print!("sol : {}", nb_sol);
         println!(" m : {}", matrix);
         assert_eq!(0, nb_sol);
@@ -1465,6 +2050,7 @@ mod test_fn_swap {
 #[cfg(test)]
 mod test_fn_sort_left {
     use super::*;
+    use crate::variable::var;
 
     #[test]
     fn sort_left_00() {
@@ -1473,18 +2059,18 @@ mod test_fn_sort_left {
         matrix[(0, 1)] = 1.into();
         matrix[(0, 2)] = 2.into();
 
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("x".to_string(), 0);
-        vars_maps.insert("y".to_string(), 1);
-        vars_maps.insert("z".to_string(), 2);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("x"), 0);
+        vars_maps.insert(var("y"), 1);
+        vars_maps.insert(var("z"), 2);
         matrix.set_vars_map(vars_maps);
 
-        let string_lst = vec![String::from("z"), String::from("y")];
+        let string_lst = vec![var("z"), var("y")];
         matrix.sort_left(string_lst);
 
-        assert_eq!(matrix.vars_map.get("z").unwrap(), &0);
-        assert_eq!(matrix.vars_map.get("y").unwrap(), &1);
-        assert_eq!(matrix.vars_map.get("x").unwrap(), &2);
+        assert_eq!(matrix.vars_map.get(&var("z")).unwrap(), &0);
+        assert_eq!(matrix.vars_map.get(&var("y")).unwrap(), &1);
+        assert_eq!(matrix.vars_map.get(&var("x")).unwrap(), &2);
         assert_eq!(matrix[(0, 0)], 2.into());
         assert_eq!(matrix[(0, 1)], 1.into());
         assert_eq!(matrix[(0, 2)], 0.into());
@@ -1503,18 +2089,18 @@ mod test_fn_sort_left {
         matrix[(2, 1)] = 1.into();
         matrix[(2, 2)] = 2.into();
 
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("x".to_string(), 0);
-        vars_maps.insert("y".to_string(), 1);
-        vars_maps.insert("z".to_string(), 2);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("x"), 0);
+        vars_maps.insert(var("y"), 1);
+        vars_maps.insert(var("z"), 2);
         matrix.set_vars_map(vars_maps);
 
-        let string_lst = vec![String::from("y"), String::from("z")];
+        let string_lst = vec![var("y"), var("z")];
         matrix.sort_left(string_lst);
 
-        assert_eq!(matrix.vars_map.get("y").unwrap(), &0);
-        assert_eq!(matrix.vars_map.get("z").unwrap(), &1);
-        assert_eq!(matrix.vars_map.get("x").unwrap(), &2);
+        assert_eq!(matrix.vars_map.get(&var("y")).unwrap(), &0);
+        assert_eq!(matrix.vars_map.get(&var("z")).unwrap(), &1);
+        assert_eq!(matrix.vars_map.get(&var("x")).unwrap(), &2);
         assert_eq!(matrix[(0, 0)], 1.into());
         assert_eq!(matrix[(1, 0)], 1.into());
         assert_eq!(matrix[(2, 0)], 1.into());
@@ -1529,12 +2115,10 @@ mod test_fn_sort_left {
 
 #[cfg(test)]
 mod test_fn_sort_right {
-    use crate::{
-        parser::{self, Parser},
-        GlobalInfos,
-    };
+    use crate::{parser::Parser, writer::write_equation, GlobalInfos};
 
     use super::*;
+    use crate::variable::var;
 
     #[test]
     fn sort_right_00() {
@@ -1543,18 +2127,18 @@ mod test_fn_sort_right {
         matrix[(0, 1)] = 1.into();
         matrix[(0, 2)] = 2.into();
 
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("x".to_string(), 0);
-        vars_maps.insert("y".to_string(), 1);
-        vars_maps.insert("z".to_string(), 2);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("x"), 0);
+        vars_maps.insert(var("y"), 1);
+        vars_maps.insert(var("z"), 2);
         matrix.set_vars_map(vars_maps);
 
-        let string_lst = vec![String::from("x"), String::from("y")];
+        let string_lst = vec![var("x"), var("y")];
         matrix.sort_right(string_lst);
 
-        assert_eq!(matrix.vars_map.get("z").unwrap(), &0);
-        assert_eq!(matrix.vars_map.get("y").unwrap(), &1);
-        assert_eq!(matrix.vars_map.get("x").unwrap(), &2);
+        assert_eq!(matrix.vars_map.get(&var("z")).unwrap(), &0);
+        assert_eq!(matrix.vars_map.get(&var("y")).unwrap(), &1);
+        assert_eq!(matrix.vars_map.get(&var("x")).unwrap(), &2);
         assert_eq!(matrix[(0, 0)], 2.into());
         assert_eq!(matrix[(0, 1)], 1.into());
         assert_eq!(matrix[(0, 2)], 0.into());
@@ -1573,18 +2157,18 @@ mod test_fn_sort_right {
         matrix[(2, 1)] = 1.into();
         matrix[(2, 2)] = 2.into();
 
-        let mut vars_maps: HashMap<String, usize> = HashMap::new();
-        vars_maps.insert("x".to_string(), 0);
-        vars_maps.insert("y".to_string(), 1);
-        vars_maps.insert("z".to_string(), 2);
+        let mut vars_maps: HashMap<Variable, usize> = HashMap::new();
+        vars_maps.insert(var("x"), 0);
+        vars_maps.insert(var("y"), 1);
+        vars_maps.insert(var("z"), 2);
         matrix.set_vars_map(vars_maps);
 
-        let string_lst = vec![String::from("x"), String::from("y")];
+        let string_lst = vec![var("x"), var("y")];
         matrix.sort_right(string_lst);
 
-        assert_eq!(matrix.vars_map.get("z").unwrap(), &0);
-        assert_eq!(matrix.vars_map.get("y").unwrap(), &1);
-        assert_eq!(matrix.vars_map.get("x").unwrap(), &2);
+        assert_eq!(matrix.vars_map.get(&var("z")).unwrap(), &0);
+        assert_eq!(matrix.vars_map.get(&var("y")).unwrap(), &1);
+        assert_eq!(matrix.vars_map.get(&var("x")).unwrap(), &2);
         assert_eq!(matrix[(0, 0)], 2.into());
         assert_eq!(matrix[(1, 0)], 2.into());
         assert_eq!(matrix[(2, 0)], 2.into());
@@ -1601,7 +2185,7 @@ mod test_fn_sort_right {
         let system: &str = "equation_system/dp_example.eqs";
 
         let mut globals: GlobalInfos = GlobalInfos::new(system.to_owned());
-        let mut parser_mod = Parser::new(&globals);
+        let mut parser_mod = Parser::new(&globals).unwrap();
 
         let mut matrix = parser_mod
             .parse_system(&mut globals)
@@ -1613,7 +2197,7 @@ mod test_fn_sort_right {
 
         let system2: &str = "equation_system/1r_3.txt";
         let mut globals: GlobalInfos = GlobalInfos::new(system2.to_owned());
-        let mut parser_mod = Parser::new(&globals);
+        let mut parser_mod = Parser::new(&globals).unwrap();
 
         let mut matrix2 = parser_mod
             .parse_system(&mut globals)
@@ -1645,7 +2229,7 @@ mod test_fn_sort_right {
         let system: &str = "equation_system/sub_our.txt";
 
         let mut globals: GlobalInfos = GlobalInfos::new(system.to_owned());
-        let mut parser_mod = Parser::new(&globals);
+        let mut parser_mod = Parser::new(&globals).unwrap();
 
         let mut our = parser_mod
             .parse_system(&mut globals)
@@ -1654,28 +2238,21 @@ mod test_fn_sort_right {
 
         let system2: &str = "equation_system/sub_true.txt";
         let mut globals: GlobalInfos = GlobalInfos::new(system2.to_owned());
-        let mut parser_mod = Parser::new(&globals);
+        let mut parser_mod = Parser::new(&globals).unwrap();
 
         let mut true_mat = parser_mod
             .parse_system(&mut globals)
             .expect("Error while parsing system");
         true_mat.set_vars_map(parser_mod.vars_map);
 
-        //Diff on vars_map
-        let our_set_vars: HashSet<String> = our.vars_map.iter().map(|(k, _)| k.clone()).collect();
-        let true_set_vars: HashSet<String> =
-            true_mat.vars_map.iter().map(|(k, _)| k.clone()).collect();
-        let inter = true_set_vars
-            .intersection(&our_set_vars)
-            .cloned()
-            .collect::<HashSet<String>>();
-        let union = true_set_vars
-            .union(&our_set_vars)
-            .cloned()
-            .collect::<HashSet<String>>();
-        let diff = union.difference(&inter);
-        println!("Diff vars_map : {:?}", diff);
-
-        assert!(our.compare(&true_mat));
+        // X_0[2,2] is only in our, true does not imply the last row of our
+        let Comparison::OnlyLeft(witness) = our.compare(&true_mat) else {
+            panic!("ERROR :: in compare_test :: our is not wider than true");
+        };
+        assert_eq!(
+            write_equation(&witness),
+            "S(K_0[3,3]) + S(X_0[0,2]) + S(X_0[1,3]) + X_0[2,0] + 0x02*S(X_0[2,0]) \
+             + X_0[2,1] + X_0[2,2] + 0x03*S(X_0[3,1])"
+        );
     }
 }
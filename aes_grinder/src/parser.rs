@@ -1,226 +1,451 @@
+use crate::field::{Field, GF2n};
+use crate::lexer::{Lexer, Token, TokenKind};
 use crate::matrix::Matrix;
+use crate::sbox::Sbox;
+use crate::var_table::VarTable;
+use crate::variable::{is_ident, Role, Variable};
 use crate::GlobalInfos;
 use log::debug;
 use log::info;
-use log::trace;
-use std::collections::HashMap;
+use log::warn;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display};
 use std::fs::File;
 use std::io::Read;
 use std::num::ParseIntError;
+use std::path::{Path, PathBuf};
 use std::vec;
 
-const MAX_NB_TERM: u8 = 20;
-const MAX_NB_MATRIX: u32 = 10;
-
-struct Reader {
+#[derive(Debug)]
+pub struct ParserError {
+    file: Option<String>,
     line: usize,
     char_: usize,
-    flow: String,
-    index: usize,
-    prev_char: Option<char>,
+    msg: String,
+    warning: bool, // reported without stopping the parsing
 }
 
-impl Reader {
-    fn new(filename: &String) -> Self {
-        let mut flow = File::open(filename).unwrap();
-        let mut us_flow = String::new();
-        flow.read_to_string(&mut us_flow).unwrap();
-
-        Reader {
-            line: 1,
-            char_: 1,
-            flow: us_flow,
-            index: 0,
-            prev_char: None,
+impl ParserError {
+    fn new(line: usize, char_: usize, msg: String) -> Self {
+        ParserError {
+            file: None,
+            line,
+            char_,
+            msg,
+            warning: false,
         }
     }
 
-    /// .
-    fn block_next(&mut self, prev_char: char) {
-        debug!("Parser::Reader::block_next");
-        self.prev_char = Some(prev_char);
+    /// Same problem, reported as a warning
+    fn into_warning(mut self) -> Self {
+        self.warning = true;
+        self
     }
 
-    fn next_char(&mut self) -> Option<char> {
-        debug!("Parser::Reader::next_char");
-        let oc: Option<char>;
-
-        match self.prev_char {
-            Some(c) => {
-                trace!(target: "parser", "catched {} - line {} - col {}", c, self.line, self.char_);
-                oc = Some(c);
-                self.prev_char = None;
-            }
-            None => {
-                oc = self.flow.chars().nth(self.index);
-                self.index += 1;
-                /*
-                oc = self.s_flow.chars().next();
-                println! ("first catch {}", oc.unwrap());
-                oc = self.s_flow.chars().next();
-                println! ("second catch {}", oc.unwrap());
-                */
-
-                match oc {
-                    Some('\n') => {
-                        self.line += 1;
-                        self.char_ = 1;
-                    }
-                    Some(c) => {
-                        trace!(target: "parser", "catched {} - line {} - col {}", c, self.line, self.char_);
-                        self.char_ += 1;
-                    }
-                    None => {}
-                }
-            }
+    /// Same error, raised in file if its file is not known yet
+    fn in_file(mut self, file: &Option<String>) -> Self {
+        if self.file.is_none() {
+            self.file = file.clone();
         }
-
-        oc
+        self
     }
-}
 
-#[derive(Debug)]
-pub struct ParserError {
-    line: usize,
-    char_: usize,
-    msg: String,
-}
-
-impl ParserError {
-    fn new(line: usize, char_: usize, msg: String) -> Self {
-        ParserError { line, char_, msg }
+    /// Error raised before parsing starts, when the system cannot be read
+    fn io(error: std::io::Error) -> Self {
+        ParserError::new(0, 0, format!("cannot read system :: {}", error))
     }
 }
 
 impl Display for ParserError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.warning {
+            write!(f, "ParserWarning")?;
+        } else {
+            write!(f, "ParserError")?;
+        }
+        if let Some(file) = &self.file {
+            write!(f, " in {}", file)?;
+        }
+        if self.line == 0 {
+            return write!(f, " :: {}", self.msg);
+        }
         write!(
             f,
-            "ParserError at line {} and char {} :: {}",
+            " at line {} and char {} :: {}",
             self.line, self.char_, self.msg
         )
     }
 }
 
-enum EndOfLineParse {
-    File,
-    Line,
-    Comment,
+impl std::error::Error for ParserError {}
+
+enum HeaderParse {
+    Section,
+    Directive,
+    None,
+}
+
+/// Headers applied as directives, never read as section names
+const DIRECTIVES: [&str; 9] = [
+    "radix",
+    "field",
+    "include",
+    "use",
+    "plaintext",
+    "ciphertext",
+    "key",
+    "state",
+    "target",
+];
+
+/// What the parser is reading
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Reading {
+    System,  // the system given to the parser
+    Include, // a file included with --include
+    Use,     // a section read again with --use
 }
 
-enum EndOfTermParse {
-    File,
-    Line,
-    Term,
+/// Source left for an include, read again once the include is done
+struct Source {
+    lexer: Lexer,
+    lookahead: Vec<Token>,
+    file: Option<String>,
+    key: String,
+    radix: u32,
+    renames: HashMap<String, String>,
+    reading: Reading,
 }
 
 pub struct Parser {
-    reader: Reader,
-    pub vars_map: HashMap<String, usize>,
+    lexer: Lexer,
+    lookahead: Vec<Token>, // tokens given back to the lexer, last is next
+    file: Option<String>,  // file being read, reported in errors
+    source: String,        // file or file#section being read, to detect include cycles
+    renames: HashMap<String, String>, // variable suffixes renamed by the include
+    reading: Reading,
+    includes: Vec<Source>, // sources to go back to, outermost first
+    pub vars_map: VarTable,
     section_name: Option<String>,
-    matrix: Vec<Vec<u32>>,
-    matrix_count: Vec<u32>,
-    var_name: Option<String>,
+    matrix: Vec<Vec<(usize, u32)>>, // sparse rows, (column, coefficient)
+    rhs: Vec<u32>,
+    constants: HashMap<Variable, u32>,
+    roles: Vec<(Variable, Role)>, // declared roles, the last matching one applies
+    sboxes: HashMap<String, Sbox>, // declared nonlinear functions
+    var_name: Option<Variable>,
+    group: Option<(Vec<(usize, u32)>, u32)>, // terms and rhs of a parenthesised sum
+    has_variables: bool,                     // a variable has been stored in the row
+    loop_indices: HashMap<String, i64>,      // values of the for loop being expanded
+    matrices: HashMap<String, Vec<Vec<u32>>>, // declared by let, rows of coefficients
+    slice: Option<i64>,                      // value of the * index
     redundancy: Option<u32>,
+    term_pos: (usize, usize),
+    collect_diagnostics: bool,
+    diagnostics: Vec<ParserError>,
+    warnings: Vec<ParserError>,
+    expanded: Vec<ParserError>, // constructs of the system written out in the rows
+    end_of_file: bool,
+    field: GF2n,
+    radix: u32,
+}
+
+/// Field of a description such as GF(2^8) / 0x11b
+fn parse_field(description: &str) -> Result<GF2n, String> {
+    let invalid = || {
+        format!(
+            "field need to be <GF(2^n) / polynomial>, not '{}'",
+            description
+        )
+    };
+    let compact: String = description.split_whitespace().collect();
+    let (degree, polynomial) = compact
+        .strip_prefix("GF(2^")
+        .and_then(|field| field.split_once(")/"))
+        .ok_or_else(invalid)?;
+
+    let degree: u32 = degree.parse().map_err(|_| invalid())?;
+    let polynomial = if let Some(digits) = polynomial.strip_prefix("0x") {
+        u32::from_str_radix(digits, 16)
+    } else if let Some(digits) = polynomial.strip_prefix("0b") {
+        u32::from_str_radix(digits, 2)
+    } else {
+        polynomial.parse()
+    }
+    .map_err(|_| invalid())?;
+
+    // coefficients are stored on 16 bits
+    if !(1..=16).contains(&degree) {
+        return Err(format!(
+            "field GF(2^{}) is not supported, n need to be 1 to 16",
+            degree
+        ));
+    }
+    if 31 - polynomial.leading_zeros() != degree {
+        return Err(format!(
+            "polynomial {:#x} is not of degree {}",
+            polynomial, degree
+        ));
+    }
+    GF2n::new(polynomial)
+}
+
+/// Byte offset following the header of the section name in src
+fn section_start(src: &str, name: &str) -> Option<usize> {
+    if DIRECTIVES.contains(&name) {
+        return None;
+    }
+    let mut lexer = Lexer::new(src.to_string());
+    loop {
+        let token = lexer.next_token();
+        match token.kind {
+            TokenKind::Header => {
+                let header = &lexer.text(&token)[2..];
+                if header
+                    .split_once(':')
+                    .is_some_and(|(n, _)| n.trim() == name)
+                {
+                    return Some(token.span.1);
+                }
+            }
+            TokenKind::Eof => return None,
+            _ => {}
+        }
+    }
 }
 
 impl Parser {
-    pub fn new(global_infos: &GlobalInfos) -> Self {
-        let reader = Reader::new(&global_infos.filename_eq_sys);
+    /// Parser reading the file named in global_infos
+    pub fn new(global_infos: &GlobalInfos) -> Result<Self, ParserError> {
+        let filename = Some(global_infos.filename_eq_sys.clone());
+        let mut parser = File::open(&global_infos.filename_eq_sys)
+            .map_err(ParserError::io)
+            .and_then(|file| Parser::from_reader(file, global_infos))
+            .map_err(|e| e.in_file(&filename))?;
+        parser.file = filename;
+        Ok(parser)
+    }
+
+    /// Parser reading a whole system from any reader
+    pub fn from_reader<R: Read>(
+        mut reader: R,
+        global_infos: &GlobalInfos,
+    ) -> Result<Self, ParserError> {
+        let mut flow = String::new();
+        reader.read_to_string(&mut flow).map_err(ParserError::io)?;
+        let field = GF2n::new(global_infos.polynomial).map_err(|e| ParserError::new(0, 0, e))?;
+        Ok(Parser {
+            field,
+            ..Parser::from_string(&flow, global_infos)
+        })
+    }
+
+    /// Parser reading a system held in memory, in the field of global_infos
+    /// or in GF(2^8) / 0x11b if its polynomial is not irreducible
+    pub fn from_string(system: &str, global_infos: &GlobalInfos) -> Self {
         Parser {
-            reader, //: Reader::new (global_infos.filename_eq_sys.clone()),
-            vars_map: HashMap::new(),
+            lexer: Lexer::new(system.to_owned()),
+            lookahead: vec![],
+            file: None,
+            source: String::new(),
+            renames: HashMap::new(),
+            reading: Reading::System,
+            includes: vec![],
+            vars_map: VarTable::new(),
             section_name: None,
             matrix: vec![],
-            matrix_count: vec![],
+            rhs: vec![],
+            constants: HashMap::new(),
+            roles: vec![],
+            sboxes: HashMap::new(),
             var_name: None,
+            group: None,
+            has_variables: false,
+            loop_indices: HashMap::new(),
+            matrices: HashMap::new(),
+            slice: None,
             redundancy: None,
+            term_pos: (1, 1),
+            collect_diagnostics: false,
+            diagnostics: vec![],
+            warnings: vec![],
+            expanded: vec![],
+            end_of_file: false,
+            field: GF2n::new(global_infos.polynomial).unwrap_or_else(|_| GF2n::aes()),
+            radix: 10,
         }
     }
 
-    fn conv_str_to_integer(&self, str: String) -> Result<u32, ParserError> {
+    /* conv_str_to_integer
+       Arguments   ::  - str : literal to convert
+
+       Description ::  convert a coefficient literal, written in the
+                       file radix or with a 0x (hexadecimal) or
+                       0b (binary) prefix, and check it is in the field
+                       in radix 16, 0b11 is the hexadecimal b11
+
+       Return      :: Ok with the value
+                      Err either
+    */
+    fn conv_str_to_integer(&self, str: &str) -> Result<u32, ParserError> {
         debug!("Parser::conv_str_to_integer");
-        let r_conv: Result<u32, ParseIntError> = str.parse::<u32>();
+        let (digits, radix) = if let Some(digits) = str.strip_prefix("0x") {
+            (digits, 16)
+        } else if let Some(digits) = str.strip_prefix("0b").filter(|_| self.radix != 16) {
+            (digits, 2)
+        } else {
+            (str, self.radix)
+        };
+        let r_conv: Result<u32, ParseIntError> = u32::from_str_radix(digits, radix);
 
         match r_conv {
+            Ok(number) if number >> self.field_degree() != 0 => Err(ParserError::new(
+                self.term_pos.0,
+                self.term_pos.1,
+                format!("coefficient {} is out of the field {}", str, self.field),
+            )),
             Ok(number) => Ok(number),
             Err(e) => Err(ParserError::new(
-                self.reader.line,
-                self.reader.char_,
-                format!("Error while parsing integer :: {}", e),
+                self.term_pos.0,
+                self.term_pos.1,
+                format!("Error while parsing integer '{}' :: {}", str, e),
             )),
         }
     }
 
-    /* skip_withespace
-       Arguments   ::
+    /// Degree of the field polynomial, coefficients are below 2^degree
+    fn field_degree(&self) -> u32 {
+        self.field.degree()
+    }
 
-       Description :: Skip all withespaces,
-                      stop before encountering an other character
+    /* report
+       Arguments   ::  - error : ParserError to report
 
-       Return      :: - true if end of file
-                      - false either
+       Description ::  In diagnostics mode, keep the error and let the caller
+                       resynchronise on the next line, otherwise propagate it
+
+       Return      :: Ok if the error has been stored
+                      Err with the error either
     */
-    fn skip_whitespaces(&mut self) -> bool {
-        debug!("Parser::skip_whitespaces");
+    fn report(&mut self, error: ParserError) -> Result<(), ParserError> {
+        let error = error.in_file(&self.file);
+        if self.collect_diagnostics {
+            debug!("diagnostic :: {}", error);
+            self.diagnostics.push(error);
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    /// Keep a warning about the input, parsing goes on
+    fn warn(&mut self, warning: ParserError) {
+        let warning = warning.into_warning().in_file(&self.file);
+        warn!("{}", warning);
+        self.warnings.push(warning);
+    }
+
+    /// Warnings raised since the last call, in file order
+    pub fn take_warnings(&mut self) -> Vec<ParserError> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Keep a construct of the system that only its expansion is read
+    /// from, the rows written back would lose it
+    fn expand(&mut self, pos: (usize, usize), construct: String) {
+        if self.reading == Reading::System {
+            let msg = format!("{} can not be written back", construct);
+            self.expanded
+                .push(ParserError::new(pos.0, pos.1, msg).in_file(&self.file));
+        }
+    }
 
-        let mut oc: Option<char> = self.reader.next_char();
+    /// Constructs of the system read so far that are expanded in the rows,
+    /// as for loops, let matrices or included files, or dropped as comments,
+    /// in file order
+    pub fn expanded(&self) -> &[ParserError] {
+        &self.expanded
+    }
+
+    /// Next token of the input, comments are skipped
+    fn next_token(&mut self) -> Token {
+        if let Some(token) = self.lookahead.pop() {
+            return token;
+        }
         loop {
-            match oc {
-                Some(c) => {
-                    if c.is_whitespace() {
-                        oc = self.reader.next_char();
-                    } else {
-                        self.reader.block_next(c);
-                        break;
-                    }
-                }
-                None => {
-                    return true;
-                }
+            let token = self.lexer.next_token();
+            if token.kind != TokenKind::Comment {
+                return token;
             }
+            self.expand(self.lexer.position(token.span.0), String::from("comment"));
         }
-        false
     }
 
-    /* pass_commentary
-       Arguments   ::
+    /// Next token of the input, left to be read again
+    fn peek_token(&mut self) -> Token {
+        let token = self.next_token();
+        self.lookahead.push(token);
+        token
+    }
+
+    /// Line and char of the next token
+    fn here(&self) -> (usize, usize) {
+        let offset = match self.lookahead.last() {
+            Some(token) => token.span.0,
+            None => self.lexer.offset(),
+        };
+        self.lexer.position(offset)
+    }
 
-       Description ::  Pass commentary,
-                       FORM OF COMMENTARY :
-                       __input__ #<MY_COMMENTARY># __input__
-                       __input__ #<MY_COMMENTARY>END_OF_LINE
-                       START_OF_LINE#<MY_COMMENTARY>END_OF_LINE
+    /// Error located at the start of token
+    fn error_at(&self, token: &Token, msg: String) -> ParserError {
+        let (line, char_) = self.lexer.position(token.span.0);
+        ParserError::new(line, char_, msg)
+    }
+
+    /* skip_line
+       Arguments   ::  - line : line to leave
+
+       Description ::  Pass every token until the parser leaves line,
+                       used to resume parsing after an error
 
        Return      ::  - true if end of file
                        - false either
     */
-    fn pass_commentary(&mut self) -> EndOfLineParse {
-        debug!("Parser::pass_commentary");
+    fn skip_line(&mut self, line: usize) -> bool {
+        debug!("Parser::skip_line");
+
+        loop {
+            let token = self.peek_token();
+            if token.kind == TokenKind::Eof {
+                return true;
+            }
+            if self.lexer.position(token.span.0).0 != line {
+                return false;
+            }
+            self.next_token();
+        }
+    }
+
+    /* skip_empty_lines
+       Arguments   ::
+
+       Description :: Skip all empty or commentary lines,
+                      stop before encountering an other token
 
-        let mut oc: Option<char> = self.reader.next_char();
+       Return      :: - true if end of file
+                      - false either
+    */
+    fn skip_empty_lines(&mut self) -> bool {
+        debug!("Parser::skip_empty_lines");
 
         loop {
-            match oc {
-                Some('\n') => {
-                    // END OF LINE
-                    return EndOfLineParse::Line;
-                }
-                Some('#') => {
-                    // END OF COMMENT
-                    return EndOfLineParse::Comment;
-                }
-                Some(_) => {
-                    // Pass char of commentary
-                }
-                None => {
-                    // END OF FILE
-                    return EndOfLineParse::File;
+            match self.peek_token().kind {
+                TokenKind::Newline => {
+                    self.next_token();
                 }
+                TokenKind::Eof => return true,
+                _ => return false,
             }
-
-            oc = self.reader.next_char();
         }
     }
 
@@ -230,136 +455,399 @@ impl Parser {
        Description ::  get the name of section and set Parser struct with.
                        section is declare like that :
                            -- my_section:
+                       a header named after a directive is applied instead :
+                           -- radix: 16
+                       in a section read with --use, the next section ends it
+                       and a nonlinear function is declared by :
+                           -- sbox S = [0x63, 0x7c, ...]
 
        Return      :: Ok with
-                       - true if section found
-                       - false either
+                       - HeaderParse::Section if section found
+                       - HeaderParse::Directive if directive applied
+                       - HeaderParse::None either
                       OR
                       Err with parsing error
     */
-    fn get_section(&mut self) -> Result<bool, ParserError> {
+    fn get_section(&mut self) -> Result<HeaderParse, ParserError> {
         debug!("Parser::get_section");
 
-        let mut oc: Option<char> = self.reader.next_char();
-
-        match oc {
-            Some('-') => {
-                // first dash CHECK
-                oc = self.reader.next_char();
-
-                match oc {
-                    Some('-') => {
-                        // second dash CHECK
-                        let mut section_name: String = String::new();
-                        let mut reach_colon: bool = false;
-                        oc = self.reader.next_char();
-
-                        loop {
-                            match oc {
-                                Some('\n') | None => {
-                                    // end of line | or end of file
-                                    if !reach_colon {
-                                        return Err(ParserError::new(
-                                            self.reader.line,
-                                            self.reader.char_,
-                                            String::from("section name need to finish by colon"),
-                                        ));
-                                    }
-                                    return Ok(true);
-                                }
-                                Some(':') => {
-                                    // reach colon
-                                    reach_colon = true;
-                                    self.section_name = Some(section_name.clone());
-                                }
-                                Some(c) => {
-                                    // other characteres
-                                    if !reach_colon {
-                                        section_name.push(c);
-                                    }
-                                }
-                            }
-
-                            oc = self.reader.next_char();
-                        }
-                    }
-                    Some(_) | None => {
-                        // NO second dash
-                        Err(ParserError::new(
-                            self.reader.line,
-                            self.reader.char_,
-                            String::from("commentary need to have two dashes"),
-                        ))
-                    }
-                }
+        let token = self.peek_token();
+        if token.kind != TokenKind::Header {
+            return Ok(HeaderParse::None);
+        }
+        self.next_token();
+
+        let header_pos = self.lexer.position(token.span.0);
+        let header = self.lexer.text(&token)[2..].to_string();
+        if let Some(declaration) = header.trim_start().strip_prefix("sbox") {
+            if declaration.starts_with(char::is_whitespace) {
+                self.declare_sbox(declaration, header_pos)?;
+                return Ok(HeaderParse::Directive);
             }
-            Some(c) => {
-                // NO first dash
-                self.reader.block_next(c);
-                Ok(false)
+        }
+        let Some((section_name, args)) = header.split_once(':') else {
+            return Err(self.error_at(&token, String::from("section name need to finish by colon")));
+        };
+
+        let section_name = section_name.trim();
+        if self.apply_directive(section_name, args.trim(), header_pos)? {
+            return Ok(HeaderParse::Directive);
+        }
+        match self.reading {
+            Reading::System => {
+                self.source = format!("{}#{}", self.file_key(), section_name);
+                self.section_name = Some(section_name.to_string());
             }
-            None => {
-                // End of file
-                Ok(false)
+            Reading::Use => {}
+            Reading::Include => {
+                return Err(self.error_at(
+                    &token,
+                    String::from("included file can not declare sections, use <--use: section>"),
+                ))
             }
         }
+        Ok(HeaderParse::Section)
     }
 
-    /* prestore_term
-       Arguments   ::  - str : &str to
-
-       Description ::  Store in self structure, redundancy or name of term, depending
-                       of the contain of str
+    /* apply_directive
+       Arguments   ::  - name : name of the header
+                       - args : text after the colon
+                       - pos : position of the header
+
+       Description ::  apply the file-level directive name, known ones are :
+                           -- radix: <2|10|16>   default base of coefficients
+                           -- include: <file> [(name=value, ...)]
+                           -- use: <section> [(name=value, ...)]
+                           -- <plaintext|ciphertext|key|state|target>: <variables>
+                       variables of a role are separated by blanks, K
+                       stands for every K_r[i,j] and K_0 for every K_0[i,j]
+                       in radix 16, a coefficient can start with a letter
+                       when it is followed by * or follows the variable
 
        Return      :: Ok with
-                       - true if store something into self structure
-                       - false either
+                       - true if name is a directive
+                       - false if name is a section
                       OR
                       Err with parsing error
     */
-    fn prestore_term(&mut self, str: &str, is_number: bool) -> Result<(), ParserError> {
-        debug!("Parser::prestore_term");
-
-        if !str.is_empty() {
-            if is_number {
-                // BUILD REDUNDANCY OF TERM
-                if self.redundancy.is_some() {
-                    return Err(ParserError::new(
-                        self.reader.line,
-                        self.reader.char_,
-                        String::from("double redundancy in sigle term, FORBIDEN!"),
-                    ));
-                }
-                self.redundancy = Some(
-                    self.conv_str_to_integer(str.to_string())
-                        .expect("Error while parsing int"),
-                );
-            } else {
-                // BUILD NAME OF TERM
-                if self.var_name.is_some() {
+    fn apply_directive(
+        &mut self,
+        name: &str,
+        args: &str,
+        pos: (usize, usize),
+    ) -> Result<bool, ParserError> {
+        debug!("Parser::apply_directive");
+
+        match name {
+            "radix" => {
+                self.radix = match args {
+                    "2" => 2,
+                    "10" => 10,
+                    "16" => 16,
+                    _ => {
+                        return Err(ParserError::new(
+                            pos.0,
+                            pos.1,
+                            format!("radix must be 2, 10 or 16, not '{}'", args),
+                        ))
+                    }
+                };
+                Ok(true)
+            }
+            "field" => {
+                let field = parse_field(args).map_err(|e| ParserError::new(pos.0, pos.1, e))?;
+                // the rows already read are in the field
+                if self.section_name.is_some() && field != self.field {
                     return Err(ParserError::new(
-                        self.reader.line,
-                        self.reader.char_,
-                        String::from("double var_name in sigle term, FORBIDEN!"),
+                        pos.0,
+                        pos.1,
+                        format!(
+                            "field is already {}, declare it before the first section",
+                            self.field
+                        ),
                     ));
                 }
-                if String::from("KV").eq(&str) {
-                    // str == KV
+                // the sboxes map elements of the field
+                if let Some((name, sbox)) = self
+                    .sboxes
+                    .iter()
+                    .find(|(_, sbox)| sbox.bits() != field.degree())
+                {
                     return Err(ParserError::new(
-                        self.reader.line,
-                        self.reader.char_,
-                        String::from(
-                            "'KV' term is an internal keyword and is forbidden for use in variable declarations!",
+                        pos.0,
+                        pos.1,
+                        format!(
+                            "sbox {} is on {} bits, not on the elements of {}",
+                            name,
+                            sbox.bits(),
+                            field
                         ),
                     ));
                 }
-                self.var_name = Some(str.to_string());
+                self.field = field;
+                Ok(true)
+            }
+            "include" | "use" => {
+                self.expand(pos, format!("--{}", name));
+                self.include(name == "use", args, pos)?;
+                Ok(true)
             }
+            _ => match name.parse::<Role>() {
+                Ok(role) => {
+                    for pattern in args.split_whitespace() {
+                        let variable = pattern
+                            .parse::<Variable>()
+                            .map_err(|e| ParserError::new(pos.0, pos.1, e))?;
+                        if variable.is_nonlinear() {
+                            return Err(ParserError::new(
+                                pos.0,
+                                pos.1,
+                                format!("role of {} is the role of its input", variable),
+                            ));
+                        }
+                        // K stands for the variables K_r[i,j] of the sections
+                        if variable.without_indices() == variable {
+                            self.expand(pos, format!("role pattern {}", variable));
+                        }
+                        self.roles.push((variable, role));
+                    }
+                    Ok(true)
+                }
+                Err(_) => Ok(false),
+            },
+        }
+    }
+
+    /* declare_sbox
+       Arguments   ::  - declaration : text after the sbox keyword
+                       - pos : position of the header
+
+       Description ::  declare a nonlinear function by its 2^n entries
+                       or as the inverse of a declared one
+                           -- sbox S = [0x63, 0x7c, ...]
+                           -- sbox Sinv = inverse(S)
+
+       Return      :: Ok if the function has been declared
+                      Err with parsing error
+    */
+    fn declare_sbox(&mut self, declaration: &str, pos: (usize, usize)) -> Result<(), ParserError> {
+        debug!("Parser::declare_sbox");
+
+        let error = |msg: String| ParserError::new(pos.0, pos.1, msg);
+        let usage = || {
+            error(String::from(
+                "sbox need to be <--sbox name = [values]> or <--sbox name = inverse(name)>",
+            ))
+        };
+        let Some((name, definition)) = declaration.split_once('=') else {
+            return Err(usage());
+        };
+        let (name, definition) = (name.trim(), definition.trim());
+        if !is_ident(name) {
+            return Err(usage());
+        }
+        if self.sboxes.contains_key(name) {
+            return Err(error(format!("sbox {} is already declared", name)));
+        }
+
+        let sbox = if let Some(values) = definition
+            .strip_prefix('[')
+            .and_then(|values| values.strip_suffix(']'))
+        {
+            self.term_pos = pos;
+            let table = values
+                .split(',')
+                .map(|value| self.conv_str_to_integer(value.trim()))
+                .collect::<Result<Vec<u32>, ParserError>>()?;
+            Sbox::new(table).map_err(error)?
+        } else if let Some(input) = definition
+            .strip_prefix("inverse(")
+            .and_then(|input| input.strip_suffix(')'))
+        {
+            let input = input.trim();
+            self.expand(pos, format!("sbox {} = inverse({})", name, input));
+            let Some(sbox) = self.sbox(input) else {
+                return Err(error(format!("unknown sbox {}", input)));
+            };
+            sbox.inverse()
+                .ok_or_else(|| error(format!("sbox {} is not invertible", input)))?
+        } else {
+            return Err(usage());
+        };
+        if sbox.bits() != self.field.degree() {
+            return Err(error(format!(
+                "sbox {} is on {} bits, not on the elements of {}",
+                name,
+                sbox.bits(),
+                self.field
+            )));
         }
 
+        debug!("sbox {} on {} bits", name, sbox.bits());
+        self.sboxes.insert(name.to_string(), sbox);
         Ok(())
     }
 
+    /// Declared nonlinear function, S is the AES S-box unless declared
+    /// in a field of 2^8 elements, it need to be declared in the others
+    fn sbox(&self, name: &str) -> Option<Sbox> {
+        match self.sboxes.get(name) {
+            Some(sbox) => Some(sbox.clone()),
+            None if name == "S" && self.field.degree() == 8 => Some(Sbox::aes()),
+            None => None,
+        }
+    }
+
+    /* include
+       Arguments   ::  - section : true to read a section of the current file,
+                                   false to read a whole file
+                       - args : <file|section> [(name=value, ...)]
+                       - pos : position of the header
+
+       Description ::  parse the rows of the file or of the section as rows
+                       of the current section, the file path is relative to
+                       the current file. Variables suffixed by a name are
+                       renamed, X_r[0,0] is read X_1[0,0] with (r=1)
+
+       Return      :: Ok if the rows have been read
+                      Err with parsing error
+    */
+    fn include(
+        &mut self,
+        section: bool,
+        args: &str,
+        pos: (usize, usize),
+    ) -> Result<(), ParserError> {
+        debug!("Parser::include");
+
+        let error = |msg: String| ParserError::new(pos.0, pos.1, msg);
+        if self.section_name.is_none() {
+            return Err(error(String::from("include need to be inside a section")));
+        }
+        let Some((target, renames)) = self.include_args(args) else {
+            return Err(error(format!(
+                "include need to be <{} (name=value, ...)>",
+                if section { "section" } else { "file" }
+            )));
+        };
+
+        let (lexer, file, key, radix) = if section {
+            let Some(start) = section_start(self.lexer.source(), &target) else {
+                return Err(error(format!("no section named '{}'", target)));
+            };
+            let lexer = Lexer::new_at(self.lexer.source().to_string(), start);
+            let key = format!("{}#{}", self.file_key(), target);
+            (lexer, self.file.clone(), key, self.radix)
+        } else {
+            let path = match self
+                .file
+                .as_deref()
+                .and_then(|file| Path::new(file).parent())
+            {
+                Some(dir) => dir.join(&target),
+                None => PathBuf::from(&target),
+            };
+            let src = std::fs::read_to_string(&path)
+                .map_err(|e| error(format!("cannot include {} :: {}", path.display(), e)))?;
+            let key = path
+                .canonicalize()
+                .map_or_else(|_| path.display().to_string(), |p| p.display().to_string());
+            (Lexer::new(src), Some(path.display().to_string()), key, 10)
+        };
+
+        if key == self.source || self.includes.iter().any(|parent| parent.key == key) {
+            let mut cycle: Vec<&str> = self.includes.iter().map(|p| p.key.as_str()).collect();
+            cycle.push(&self.source);
+            cycle.push(&key);
+            return Err(error(format!("include cycle :: {}", cycle.join(" -> "))));
+        }
+
+        // Read the include in place of the current source
+        let reading = if section {
+            Reading::Use
+        } else {
+            Reading::Include
+        };
+        let parent = Source {
+            lexer: std::mem::replace(&mut self.lexer, lexer),
+            lookahead: std::mem::take(&mut self.lookahead),
+            file: std::mem::replace(&mut self.file, file),
+            key: std::mem::replace(&mut self.source, key),
+            radix: std::mem::replace(&mut self.radix, radix),
+            renames: std::mem::replace(&mut self.renames, renames),
+            reading: std::mem::replace(&mut self.reading, reading),
+        };
+        self.includes.push(parent);
+
+        let result = self.parse_rows().map_err(|e| e.in_file(&self.file));
+
+        let parent = self.includes.pop().unwrap();
+        self.lexer = parent.lexer;
+        self.lookahead = parent.lookahead;
+        self.file = parent.file;
+        self.source = parent.key;
+        self.radix = parent.radix;
+        self.renames = parent.renames;
+        self.reading = parent.reading;
+
+        result.map(|_| ())
+    }
+
+    /* include_args
+       Arguments   ::  - args : <target> [(name=value, ...)]
+
+       Description ::  split the arguments of an include, a value being a
+                       name renamed in the current source is renamed too
+
+       Return      ::  Some with the target and the renaming
+                       None if args are invalid
+    */
+    fn include_args(&self, args: &str) -> Option<(String, HashMap<String, String>)> {
+        let (target, list) = match args.split_once('(') {
+            Some((target, list)) => (target.trim(), list.trim_end().strip_suffix(')')?),
+            None => (args.trim(), ""),
+        };
+        let is_word = |str: &str| {
+            !str.is_empty() && str.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'_')
+        };
+        if target.is_empty() {
+            return None;
+        }
+
+        let mut renames = HashMap::new();
+        for rename in list.split(',').filter(|rename| !rename.trim().is_empty()) {
+            let (name, value) = rename.split_once('=')?;
+            let (name, value) = (name.trim(), value.trim());
+            if !is_word(name) || !is_word(value) {
+                return None;
+            }
+            let value = self.renames.get(value).map_or(value, String::as_str);
+            renames.insert(name.to_string(), value.to_string());
+        }
+        Some((target.to_string(), renames))
+    }
+
+    /// Identifier with its suffix renamed by the include being read
+    fn rename(&self, ident: &str) -> String {
+        match ident.rsplit_once('_') {
+            Some((base, suffix)) if !base.is_empty() => match self.renames.get(suffix) {
+                Some(value) => format!("{}_{}", base, value),
+                None => ident.to_string(),
+            },
+            _ => ident.to_string(),
+        }
+    }
+
+    /// Canonical path of the file being read, empty for a system in memory
+    fn file_key(&self) -> String {
+        match &self.file {
+            Some(file) => Path::new(file)
+                .canonicalize()
+                .map_or_else(|_| file.clone(), |path| path.display().to_string()),
+            None => String::new(),
+        }
+    }
+
     /* get_term
        Arguments   ::
 
@@ -369,361 +857,2355 @@ impl Parser {
                       term :: + <(Redundancy) * (Var)> +
                       possible to have redundancy or var or both
 
-       Return      :: Ok with possible EndOfTermParse
-                      Err if an IO error
+       Return      :: Ok with the token ending the term, not consumed
+                      Err with parsing error
     */
-    fn get_term(&mut self) -> Result<EndOfTermParse, ParserError> {
+    fn get_term(&mut self) -> Result<Token, ParserError> {
         debug!("Parser::get_term");
 
-        let mut is_number: bool = true;
-        let mut blank_appear_inside_str: bool = false;
-        let mut str: String = String::new();
-
-        while let Some(char_) = self.reader.next_char() {
-            match char_ {
-                '#' => {
-                    // START OF COMMENTARY
-                    match self.pass_commentary() {
-                        EndOfLineParse::File => {
-                            self.prestore_term(&str, is_number)
-                                .expect("Erro while prestore_term");
-                            return Ok(EndOfTermParse::File);
+        let mut separated: bool = true; // factors need to be separated by *
+
+        loop {
+            let token = self.peek_token();
+            match token.kind {
+                TokenKind::Star => {
+                    // MIDDLE OF TERM
+                    self.next_token();
+                    separated = true;
+                }
+                TokenKind::Ident if self.matrices.contains_key(self.lexer.text(&token)) => {
+                    // PRODUCT OF A MATRIX AND A VECTOR, the row of the slice
+                    if !separated || self.var_name.is_some() || self.group.is_some() {
+                        return Err(self.error_at(
+                            &token,
+                            String::from("a term can only multiply one variable or sum"),
+                        ));
+                    }
+                    separated = false;
+                    self.term_pos = self.lexer.position(token.span.0);
+                    self.next_token();
+                    self.group = Some(self.parse_product(&token)?);
+                }
+                TokenKind::Number | TokenKind::Ident => {
+                    let coefficient =
+                        token.kind == TokenKind::Number || self.is_hex_coefficient(&token);
+                    // CHECK THAT REDUNDANCY AND NAME are separate with *
+                    if !separated {
+                        return Err(self.error_at(
+                            &token,
+                            String::from("impossible to have following strings without '*' or '+'"),
+                        ));
+                    }
+                    separated = false;
+                    self.term_pos = self.lexer.position(token.span.0);
+
+                    if coefficient {
+                        // BUILD REDUNDANCY OF TERM
+                        if self.redundancy.is_some() {
+                            return Err(self.error_at(
+                                &token,
+                                String::from("double redundancy in sigle term, FORBIDEN!"),
+                            ));
                         }
-                        EndOfLineParse::Line => {
-                            self.prestore_term(&str, is_number)
-                                .expect("Erro while prestore_term");
-                            return Ok(EndOfTermParse::Line);
+                        self.next_token();
+                        self.redundancy = Some(self.conv_str_to_integer(self.lexer.text(&token))?);
+                    } else {
+                        // BUILD NAME OF TERM
+                        if self.var_name.is_some() || self.group.is_some() {
+                            return Err(self.error_at(
+                                &token,
+                                String::from("double var_name in sigle term, FORBIDEN!"),
+                            ));
                         }
-                        EndOfLineParse::Comment => {}
+                        self.var_name = Some(self.parse_variable()?);
                     }
                 }
-                '+' => {
-                    // END OF TERM
-                    self.prestore_term(&str, is_number)
-                        .expect("Error while prestore_term");
-                    return Ok(EndOfTermParse::Term);
-                }
-                '\n' => {
-                    // END OF LINE
-                    self.prestore_term(&str, is_number)
-                        .expect("Error while prestore_term");
-                    return Ok(EndOfTermParse::Line);
-                }
-                '*' => {
-                    // MIDDLE OF TERM
-                    self.prestore_term(&str, is_number)
-                        .expect("Error while prestore_term");
-                    str.clear(); // clean string for next
-                    is_number = true; // reset for next
+                TokenKind::LParen => {
+                    // PARENTHESISED SUM, multiplied by the rest of the term
+                    if !separated || self.var_name.is_some() || self.group.is_some() {
+                        return Err(self.error_at(
+                            &token,
+                            String::from("a term can only multiply one variable or sum"),
+                        ));
+                    }
+                    separated = false;
+                    self.next_token();
+                    self.group = Some(self.parse_group(&token)?);
                 }
-                c => {
-                    // BUILD TERM PART - REDUNDANCY OR NAME
-                    if c.is_whitespace() {
-                        blank_appear_inside_str = true;
-                    } else {
-                        // CHECK THAT REDUNDANCY AND NAME are separate with *
-                        if !str.is_empty() && blank_appear_inside_str {
-                            return Err(ParserError::new(
-                                self.reader.line,
-                                self.reader.char_,
+                _ => return Ok(token),
+            }
+        }
+    }
+
+    /// True if the identifier is a coefficient of radix 16, as c6 in
+    /// c6*X or X*c6, a constant stays a variable
+    fn is_hex_coefficient(&mut self, token: &Token) -> bool {
+        let text = self.lexer.text(token);
+        if self.radix != 16
+            || !text.chars().all(|c| c.is_ascii_hexdigit())
+            || self.constants.contains_key(&Variable::new(text, vec![]))
+        {
+            return false;
+        }
+        if self.var_name.is_some() {
+            return true;
+        }
+        let token = self.next_token();
+        let next = self.peek_token();
+        self.lookahead.push(token);
+        next.kind == TokenKind::Star
+    }
+
+    /* parse_variable
+       Arguments   ::
+
+       Description ::  read a variable, with its indices
+                       or the variable its function is applied on
+                           name | name[i,j] | S(name[i,j])
+
+       Return      ::  Ok with the variable
+                       Err with parsing error
+    */
+    fn parse_variable(&mut self) -> Result<Variable, ParserError> {
+        debug!("Parser::parse_variable");
+
+        let token = self.next_token();
+        let name = self.lexer.text(&token).to_string();
+        let mut indices: Vec<usize> = vec![];
+
+        match self.peek_token().kind {
+            TokenKind::LBracket => {
+                self.next_token();
+                loop {
+                    let start = self.peek_token();
+                    let index = self.parse_index()?;
+                    match usize::try_from(index) {
+                        Ok(i) => indices.push(i),
+                        Err(_) => {
+                            return Err(self.error_at(&start, format!("negative index {}", index)))
+                        }
+                    }
+
+                    let separator = self.next_token();
+                    match separator.kind {
+                        TokenKind::Comma => {}
+                        TokenKind::RBracket => break,
+                        _ => {
+                            return Err(self.error_at(
+                                &separator,
                                 String::from(
-                                    "impossible to have following strings without '*' or '+'",
+                                    "indices need to be separated by ',' and closed by ']'",
                                 ),
-                            ));
+                            ))
                         }
+                    }
+                }
+            }
+            TokenKind::LParen => {
+                if self.sbox(&name).is_none() {
+                    return Err(self.error_at(
+                        &token,
+                        format!(
+                            "unknown function {}, declare it with <--sbox {} = [values]>",
+                            name, name
+                        ),
+                    ));
+                }
+                self.next_token();
+                let argument = self.peek_token();
+                if argument.kind != TokenKind::Ident {
+                    return Err(self.error_at(
+                        &argument,
+                        format!("{} need to be applied on a variable", name),
+                    ));
+                }
+                let input = self.parse_variable()?;
+                if input.is_nonlinear() {
+                    return Err(self.error_at(
+                        &argument,
+                        format!("{} can not be applied on {}", name, input),
+                    ));
+                }
 
-                        if str.is_empty() {
-                            // unset blank_appear at start of new string
-                            blank_appear_inside_str = false;
-                        }
+                let close = self.next_token();
+                if close.kind != TokenKind::RParen {
+                    return Err(self.error_at(&close, String::from("missing ')'")));
+                }
+                return Ok(input.apply(&name));
+            }
+            _ if self.loop_indices.contains_key(&name) => {
+                return Err(self.error_at(
+                    &token,
+                    format!("loop index {} can only be used in indices", name),
+                ))
+            }
+            _ => {}
+        }
 
-                        if is_number && c.is_alphabetic() {
-                            // set for valid prestore_term call
-                            is_number = false;
-                        }
+        Ok(Variable::new(&self.rename(&name), indices))
+    }
 
-                        str.push(c);
-                    }
+    /* parse_index
+       Arguments   ::
+
+       Description ::  read an index, an integer expression on the
+                       indices of the for loop being expanded
+                           index   :: product (+|- product)*
+                           product :: factor (*|/|% factor)*
+                           factor  :: number | loop index | (index)
+                       / and % round toward minus infinity, (i-1)%4 is in 0..4
+
+       Return      ::  Ok with the value of the index
+                       Err with parsing error
+    */
+    fn parse_index(&mut self) -> Result<i64, ParserError> {
+        debug!("Parser::parse_index");
+
+        let mut value = self.parse_index_product()?;
+        loop {
+            let op = self.peek_token();
+            let rhs = match op.kind {
+                TokenKind::Plus | TokenKind::Minus => {
+                    self.next_token();
+                    self.parse_index_product()?
+                }
+                _ => return Ok(value),
+            };
+            let result = match op.kind {
+                TokenKind::Plus => value.checked_add(rhs),
+                _ => value.checked_sub(rhs),
+            };
+            value = result.ok_or_else(|| self.error_at(&op, String::from("index overflow")))?;
+        }
+    }
+
+    fn parse_index_product(&mut self) -> Result<i64, ParserError> {
+        let mut value = self.parse_index_factor()?;
+        loop {
+            let op = self.peek_token();
+            let rhs = match op.kind {
+                TokenKind::Star | TokenKind::Slash | TokenKind::Percent => {
+                    self.next_token();
+                    self.parse_index_factor()?
+                }
+                _ => return Ok(value),
+            };
+            if rhs == 0 && op.kind != TokenKind::Star {
+                return Err(self.error_at(&op, String::from("division by zero in index")));
+            }
+            let result = match op.kind {
+                TokenKind::Star => value.checked_mul(rhs),
+                TokenKind::Slash => value.checked_div_euclid(rhs),
+                _ => value.checked_rem_euclid(rhs),
+            };
+            value = result.ok_or_else(|| self.error_at(&op, String::from("index overflow")))?;
+        }
+    }
+
+    fn parse_index_factor(&mut self) -> Result<i64, ParserError> {
+        let token = self.next_token();
+        let text = self.lexer.text(&token);
+        match token.kind {
+            TokenKind::Number => text
+                .parse::<i64>()
+                .map_err(|_| self.error_at(&token, format!("invalid index '{}'", text))),
+            TokenKind::Star => self.slice.ok_or_else(|| {
+                self.error_at(
+                    &token,
+                    String::from("'*' index need a matrix in the equation to give its size"),
+                )
+            }),
+            TokenKind::Ident => match self.loop_indices.get(text) {
+                Some(&value) => Ok(value),
+                None => Err(self.error_at(&token, format!("unknown loop index '{}'", text))),
+            },
+            TokenKind::LParen => {
+                let value = self.parse_index()?;
+                let close = self.next_token();
+                if close.kind != TokenKind::RParen {
+                    return Err(self.error_at(&close, String::from("missing ')'")));
                 }
+                Ok(value)
             }
+            _ => Err(self.error_at(&token, format!("invalid index '{}'", text))),
         }
+    }
 
-        // NO MORE CHARACTER
-        self.prestore_term(&str, is_number)
-            .expect("Error while affecting string");
+    /* get_vec_ndx
+       Arguments   ::
+
+       Description ::  get the index in which column self.var_name
+                       need to be stored
+
+       Return      :: Some with the valid index
+                      None if the term is a constant
+    */
+    fn get_vec_ndx(&mut self) -> Option<usize> {
+        debug!("Parser::get_vec_index");
+
+        // See if variable in term
+        let var: &Variable = match &self.var_name {
+            Some(v) if !self.constants.contains_key(&v.input()) => v,
+            _ => return None,
+        };
+
+        // Search in map if variable exist
+        match self.vars_map.get(var) {
+            Some(&index) => Some(index),
+            None => {
+                // Create new index
+                let index = self.vars_map.len();
+                debug!("{} at index {}", var, index);
+
+                self.vars_map.insert(var.clone(), index);
+
+                Some(index)
+            }
+        }
+    }
+
+    /* store_term
+       Arguments   ::
+
+       Description :: store the term in matrix
+
+       Return      :: Ok
+                      Err either
+    */
+    fn store_term(&mut self) -> Result<bool, ParserError> {
+        debug!("Parser::store_term");
+
+        if self.redundancy.is_none() && self.var_name.is_none() && self.group.is_none() {
+            return Ok(false);
+        }
+
+        let rdd = self.redundancy.unwrap_or(1);
+        let line = self.matrix.len() - 1;
+
+        if let Some((terms, rhs)) = self.group.take() {
+            // DISTRIBUTE REDUNDANCY ON THE SUM
+            for (index, coef) in terms {
+                let coef = self.field_mul(rdd, coef)?;
+                self.add_coef(line, index, coef)?;
+            }
+            self.rhs[line] ^= self.field_mul(rdd, rhs)?;
+        } else if let Some(index) = Parser::get_vec_ndx(self) {
+            // SET REDUNDANCY ON MATRIX
+            self.has_variables = true;
+            if self.add_coef(line, index, rdd)? {
+                // repeated variable, its coefficients are added
+                let (line_, char_) = self.term_pos;
+                let name = self
+                    .var_name
+                    .as_ref()
+                    .map_or(String::new(), |v| v.to_string());
+                self.warn(ParserError::new(
+                    line_,
+                    char_,
+                    format!("{} is repeated, its coefficients are added", name),
+                ));
+            }
+        } else {
+            // KNOWN VALUE, moved to the right hand side
+            let value = match &self.var_name {
+                Some(name) => self.field_mul(rdd, self.constant_value(name)?)?,
+                None => rdd,
+            };
+            self.rhs[line] ^= value;
+        }
+
+        // Reset term after storing
+        self.redundancy = None;
+        self.var_name = None;
+
+        Ok(true)
+    }
+
+    /// Add coef to the column index of the row line, true if the column
+    /// already had a term
+    fn add_coef(&mut self, line: usize, index: usize, coef: u32) -> Result<bool, ParserError> {
+        match self.matrix[line]
+            .iter()
+            .position(|&(column, _)| column == index)
+        {
+            Some(term) => {
+                let coef = self.field_add(self.matrix[line][term].1, coef)?;
+                if coef == 0 {
+                    self.matrix[line].remove(term);
+                } else {
+                    self.matrix[line][term].1 = coef;
+                }
+                Ok(true)
+            }
+            None => {
+                self.matrix[line].push((index, coef));
+                Ok(false)
+            }
+        }
+    }
+
+    /* parse_group
+       Arguments   ::  - open : the '(' already read
+
+       Description ::  parse the terms of a parenthesised sum up to its ')',
+                       in a row of its own, sums can be nested
+                           02*(S(a) + 03*(b + c)) + (K_0 + K_1)
+
+       Return      ::  Ok with the terms and the rhs of the sum
+                       Err either
+    */
+    fn parse_group(&mut self, open: &Token) -> Result<(Vec<(usize, u32)>, u32), ParserError> {
+        debug!("Parser::parse_group");
+
+        // the term holding the sum goes on once the sum is read
+        let term = (self.redundancy.take(), self.var_name.take());
+        let line = self.matrix.len() - 1;
+        let row = std::mem::take(&mut self.matrix[line]);
+        let rhs = std::mem::replace(&mut self.rhs[line], 0);
+
+        let mut nb_terms: usize = 0;
+        loop {
+            let end = self.get_term()?;
+            if self.store_term()? {
+                nb_terms += 1;
+            }
+            match end.kind {
+                TokenKind::Plus => {
+                    self.next_token();
+                }
+                TokenKind::RParen => {
+                    self.next_token();
+                    break;
+                }
+                _ => return Err(self.error_at(open, String::from("'(' is never closed"))),
+            }
+        }
+        if nb_terms == 0 {
+            return Err(self.error_at(open, String::from("empty parenthesis")));
+        }
+
+        let group = (
+            std::mem::replace(&mut self.matrix[line], row),
+            std::mem::replace(&mut self.rhs[line], rhs),
+        );
+        (self.redundancy, self.var_name) = term;
+        Ok(group)
+    }
+
+    /* parse_line
+       Arguments   ::
+
+       Description ::  parse line of the input file,
+                       a line starting by the const or let keyword is a
+                       declaration and a line starting by the for keyword
+                       is a loop
+
+       Return      ::  Ok if the line has been read
+                       Err either
+    */
+    fn parse_line(&mut self) -> Result<(), ParserError> {
+        debug!("Parser::parse_line");
+
+        let first = self.next_token();
+        let next = self.peek_token().kind;
+        if first.kind == TokenKind::Ident {
+            let construct = match self.lexer.text(&first) {
+                "const" => Some("const declaration"),
+                "let" => Some("let matrix"),
+                "for" => Some("for loop"),
+                _ => None,
+            };
+            if let Some(construct) = construct {
+                self.expand(self.lexer.position(first.span.0), construct.to_string());
+            }
+            match (self.lexer.text(&first), next) {
+                ("const", TokenKind::Ident | TokenKind::Number) => return self.parse_const(),
+                ("let", TokenKind::Ident) => return self.parse_let(),
+                ("for", TokenKind::Ident) => {
+                    let result = self.parse_for();
+                    self.loop_indices.clear();
+                    return result;
+                }
+                _ => {}
+            }
+        }
+        self.lookahead.push(first);
+        self.parse_slices()
+    }
+
+    /* parse_slices
+       Arguments   ::
+
+       Description ::  parse an equation using declared matrices once for
+                       each row of the matrices, the * index being the row
+                           W_0[*,0] = MC * S(X_0[*,0])
+                       other equations are parsed once
+
+       Return      ::  Ok if every row has been stored
+                       Err either
+    */
+    fn parse_slices(&mut self) -> Result<(), ParserError> {
+        debug!("Parser::parse_slices");
+
+        let mut body: Vec<Token> = vec![];
+        let end = loop {
+            let token = self.next_token();
+            match token.kind {
+                TokenKind::Newline | TokenKind::Eof => break token,
+                _ => body.push(token),
+            }
+        };
+
+        // Every matrix of the equation gives the number of rows
+        let mut size: Option<usize> = None;
+        for token in body.iter().filter(|t| t.kind == TokenKind::Ident) {
+            let Some(matrix) = self.matrices.get(self.lexer.text(token)) else {
+                continue;
+            };
+            match size {
+                Some(rows) if rows != matrix.len() => {
+                    return Err(self.error_at(
+                        token,
+                        format!(
+                            "matrix {} has {} rows, not {} as the other matrices",
+                            self.lexer.text(token),
+                            matrix.len(),
+                            rows
+                        ),
+                    ));
+                }
+                _ => size = Some(matrix.len()),
+            }
+        }
+
+        for row in 0..size.unwrap_or(1) {
+            self.slice = size.map(|_| row as i64);
+            self.lookahead.push(end);
+            self.lookahead.extend(body.iter().rev());
+            self.parse_equation()?;
+        }
+        self.slice = None;
+        Ok(())
+    }
+
+    /* parse_product
+       Arguments   ::  - name : the matrix already read
+
+       Description ::  parse the vector multiplied by the matrix, a variable
+                       or a parenthesised sum using the * index, read again
+                       for each column with * the column
+                           MC * S(X_0[*,(j+*)%4])
+                       the vector can not hold another product
+
+       Return      ::  Ok with the terms and the rhs of the row of the product
+                       Err either
+    */
+    fn parse_product(&mut self, name: &Token) -> Result<(Vec<(usize, u32)>, u32), ParserError> {
+        debug!("Parser::parse_product");
+
+        let matrix = self.matrices[self.lexer.text(name)].clone();
+        let Some(row) = self.slice else {
+            unreachable!("matrices are only read by parse_slices")
+        };
+
+        let star = self.next_token();
+        let first = self.peek_token();
+        if star.kind != TokenKind::Star
+            || !matches!(first.kind, TokenKind::Ident | TokenKind::LParen)
+        {
+            return Err(self.error_at(
+                name,
+                String::from("matrix need to multiply a vector <M * v[*]>"),
+            ));
+        }
+
+        // the term holding the product goes on once the product is read
+        let term = (self.redundancy.take(), self.var_name.take());
+        let line = self.matrix.len() - 1;
+        let saved = (
+            std::mem::take(&mut self.matrix[line]),
+            std::mem::replace(&mut self.rhs[line], 0),
+        );
+
+        // Keep the vector, it is read again for each column
+        let mut vector: Vec<Token> = vec![];
+        let mut depth: usize = 0;
+        loop {
+            let token = self.next_token();
+            match token.kind {
+                TokenKind::LParen | TokenKind::LBracket => depth += 1,
+                TokenKind::RParen | TokenKind::RBracket => depth -= 1,
+                TokenKind::Newline | TokenKind::Eof | TokenKind::Header => {
+                    self.lookahead.push(token);
+                    return Err(self.error_at(&first, String::from("'(' is never closed")));
+                }
+                _ => {}
+            }
+            vector.push(token);
+            let next = self.peek_token().kind;
+            if depth == 0 && !matches!(next, TokenKind::LParen | TokenKind::LBracket) {
+                break;
+            }
+        }
+        // * is the column of this product in the vector, not a row of another
+        if let Some(nested) = vector.iter().find(|token| {
+            token.kind == TokenKind::Ident && self.matrices.contains_key(self.lexer.text(token))
+        }) {
+            return Err(self.error_at(
+                nested,
+                String::from("a matrix product can not be in the vector of another one"),
+            ));
+        }
+
+        for (column, &coef) in matrix[row as usize].iter().enumerate() {
+            self.slice = Some(column as i64);
+            self.lookahead.extend(vector.iter().rev());
+            let open = self.next_token();
+            if open.kind == TokenKind::LParen {
+                self.group = Some(self.parse_group(&open)?);
+            } else {
+                self.lookahead.push(open);
+                self.var_name = Some(self.parse_variable()?);
+            }
+            if coef == 0 {
+                self.var_name = None;
+                self.group = None;
+                continue;
+            }
+            self.redundancy = Some(coef);
+            self.store_term()?;
+        }
+        self.slice = Some(row);
+
+        let product = (
+            std::mem::replace(&mut self.matrix[line], saved.0),
+            std::mem::replace(&mut self.rhs[line], saved.1),
+        );
+        (self.redundancy, self.var_name) = term;
+        Ok(product)
+    }
+
+    /* parse_equation
+       Arguments   ::
+
+       Description ::  parse the terms of an equation up to the end of line
+                           term + term + ... [= term + ...]
+
+       Return      ::  Ok if the equation has been stored
+                       Err either
+    */
+    fn parse_equation(&mut self) -> Result<(), ParserError> {
+        debug!("Parser::parse_equation");
+
+        let mut nb_terms: usize = 0;
+        let mut lhs_terms: Option<(usize, Token)> = None; // number of terms and the =
+        let start = self.peek_token();
+        self.has_variables = false;
+        self.matrix.push(vec![]);
+        self.rhs.push(0);
+
+        loop {
+            let end = self.get_term()?;
+            if self.store_term()? {
+                nb_terms += 1;
+            }
+            self.next_token();
+
+            match end.kind {
+                TokenKind::Plus => {}
+                TokenKind::Equal => {
+                    // END OF LEFT HAND SIDE
+                    if lhs_terms.is_some() {
+                        return Err(self.error_at(
+                            &end,
+                            String::from("only one '=' is allowed in an equation"),
+                        ));
+                    }
+                    lhs_terms = Some((nb_terms, end));
+                }
+                TokenKind::Newline | TokenKind::Eof => break,
+                TokenKind::Header => {
+                    return Err(self.error_at(
+                        &end,
+                        String::from("section need to be declared on its own line"),
+                    ))
+                }
+                _ => {
+                    return Err(
+                        self.error_at(&end, format!("unexpected '{}'", self.lexer.text(&end)))
+                    )
+                }
+            }
+        }
+
+        if let Some((lhs_terms, equal)) = lhs_terms {
+            // a = b is a + b = 0 in characteristic 2, only check both sides exist
+            if lhs_terms == 0 || lhs_terms == nb_terms {
+                return Err(self.error_at(&equal, String::from("empty side in equation")));
+            }
+        }
+        if self.has_variables && self.matrix.last().is_some_and(|row| row.is_empty()) {
+            // every variable cancelled out, 0 = rhs
+            if self.rhs.last() != Some(&0) {
+                return Err(self.error_at(
+                    &start,
+                    String::from("terms cancel out, the equation has no solution"),
+                ));
+            }
+            self.warn(self.error_at(
+                &start,
+                String::from("terms cancel out, the equation is dropped"),
+            ));
+            nb_terms = 0;
+        }
+        if nb_terms == 0 {
+            self.matrix.pop();
+            self.rhs.pop();
+        }
+        Ok(())
+    }
+
+    /* parse_for
+       Arguments   ::
+
+       Description ::  parse the end of a loop line, its equation is
+                       expanded in one row per value of the indices
+                           for i in 0..4, j in 0..4: W_0[i,j] + K_1[i,j]
+                       bounds are constant integer expressions, the end one
+                       excluded, the first index is the outer loop
+
+       Return      ::  Ok if every row has been stored
+                       Err either
+    */
+    fn parse_for(&mut self) -> Result<(), ParserError> {
+        debug!("Parser::parse_for");
+
+        let mut loops: Vec<(String, i64, i64)> = vec![];
+        loop {
+            let name = self.next_token();
+            let index = self.lexer.text(&name).to_string();
+            if name.kind != TokenKind::Ident {
+                return Err(self.error_at(&name, format!("invalid loop index '{}'", index)));
+            }
+            if loops.iter().any(|(other, _, _)| *other == index) {
+                return Err(self.error_at(&name, format!("loop index {} is already used", index)));
+            }
+
+            let keyword = self.next_token();
+            if keyword.kind != TokenKind::Ident || self.lexer.text(&keyword) != "in" {
+                return Err(self.error_at(
+                    &keyword,
+                    String::from("loop need to be <for i in start..end: equation>"),
+                ));
+            }
+            let start = self.parse_index()?;
+            let range = self.next_token();
+            if range.kind != TokenKind::Range {
+                return Err(self.error_at(
+                    &range,
+                    String::from("loop need to be <for i in start..end: equation>"),
+                ));
+            }
+            let end = self.parse_index()?;
+            loops.push((index, start, end));
+
+            let separator = self.next_token();
+            match separator.kind {
+                TokenKind::Comma => {}
+                TokenKind::Colon => break,
+                _ => {
+                    return Err(self.error_at(
+                        &separator,
+                        String::from("loops need to be separated by ',' and closed by ':'"),
+                    ))
+                }
+            }
+        }
+
+        // Keep the equation, it is read again for each row
+        let mut body: Vec<Token> = vec![];
+        let end = loop {
+            let token = self.next_token();
+            match token.kind {
+                TokenKind::Newline | TokenKind::Eof => break token,
+                _ => body.push(token),
+            }
+        };
+        if body.is_empty() {
+            return Err(self.error_at(&end, String::from("loop without equation")));
+        }
+
+        self.expand_for(&loops, &body, end)
+    }
+
+    /* expand_for
+       Arguments   ::  - loops : (index, start, end) of the loops left to expand
+                       - body : tokens of the equation
+                       - end : token ending the loop line
+
+       Description ::  parse body once for each value of the loop indices
+
+       Return      ::  Ok if every row has been stored
+                       Err either
+    */
+    fn expand_for(
+        &mut self,
+        loops: &[(String, i64, i64)],
+        body: &[Token],
+        end: Token,
+    ) -> Result<(), ParserError> {
+        let Some(((index, start, stop), inner)) = loops.split_first() else {
+            // Every index has a value, give the equation back to the parser
+            self.lookahead.push(end);
+            self.lookahead.extend(body.iter().rev());
+            return self.parse_slices();
+        };
+
+        for value in *start..*stop {
+            self.loop_indices.insert(index.clone(), value);
+            self.expand_for(inner, body, end)?;
+        }
+        Ok(())
+    }
+
+    /* parse_const
+       Arguments   ::
+
+       Description ::  parse the end of a constant declaration line
+                           const <name> = <value>
+
+       Return      ::  Ok if the constant has been declared
+                       Err either
+    */
+    fn parse_const(&mut self) -> Result<(), ParserError> {
+        debug!("Parser::parse_const");
+
+        let name = self.next_token();
+        if name.kind != TokenKind::Ident {
+            return Err(self.error_at(
+                &name,
+                format!("invalid constant name '{}'", self.lexer.text(&name)),
+            ));
+        }
+        let constant = Variable::new(self.lexer.text(&name), vec![]);
+        if self.vars_map.contains_key(&constant) || self.constants.contains_key(&constant) {
+            return Err(self.error_at(&name, format!("'{}' is already declared", constant)));
+        }
+
+        // Stop on the first unexpected token, it may end the line
+        let equal = self.next_token();
+        if equal.kind != TokenKind::Equal {
+            return Err(self.error_at(
+                &equal,
+                String::from("constant declaration need to be <const name = value>"),
+            ));
+        }
+        let value = self.next_token();
+        if value.kind != TokenKind::Number {
+            return Err(self.error_at(
+                &value,
+                String::from("constant declaration need to be <const name = value>"),
+            ));
+        }
+        self.term_pos = self.lexer.position(value.span.0);
+        let value = self.conv_str_to_integer(self.lexer.text(&value))?;
+
+        let end = self.next_token();
+        if !matches!(end.kind, TokenKind::Newline | TokenKind::Eof) {
+            return Err(self.error_at(
+                &end,
+                String::from("constant declaration need to be <const name = value>"),
+            ));
+        }
+
+        debug!("const {} = {}", constant, value);
+        self.constants.insert(constant, value);
+
+        Ok(())
+    }
+
+    /* parse_let
+       Arguments   ::
+
+       Description ::  parse the end of a matrix declaration line, its
+                       coefficients are given row by row
+                           let MC = [[2,3,1,1],[1,2,3,1],[1,1,2,3],[3,1,1,2]]
+
+       Return      ::  Ok if the matrix has been declared
+                       Err either
+    */
+    fn parse_let(&mut self) -> Result<(), ParserError> {
+        debug!("Parser::parse_let");
+
+        let usage = || String::from("matrix declaration need to be <let name = [[a, b], [c, d]]>");
+        let name = self.next_token();
+        let text = self.lexer.text(&name).to_string();
+        let variable = Variable::new(&text, vec![]);
+        if self.matrices.contains_key(&text)
+            || self.sboxes.contains_key(&text)
+            || self.constants.contains_key(&variable)
+            || self
+                .vars_map
+                .keys()
+                .any(|var| var.input().matches(&variable))
+        {
+            return Err(self.error_at(&name, format!("'{}' is already declared", text)));
+        }
+
+        let equal = self.next_token();
+        let open = self.next_token();
+        if equal.kind != TokenKind::Equal || open.kind != TokenKind::LBracket {
+            return Err(self.error_at(&equal, usage()));
+        }
+
+        let mut matrix: Vec<Vec<u32>> = vec![];
+        loop {
+            let open_row = self.next_token();
+            if open_row.kind != TokenKind::LBracket {
+                return Err(self.error_at(&open_row, usage()));
+            }
+            let mut row = vec![];
+            loop {
+                let value = self.next_token();
+                if value.kind != TokenKind::Number {
+                    return Err(self.error_at(&value, usage()));
+                }
+                self.term_pos = self.lexer.position(value.span.0);
+                row.push(self.conv_str_to_integer(self.lexer.text(&value))?);
+                let next = self.next_token();
+                match next.kind {
+                    TokenKind::Comma => {}
+                    TokenKind::RBracket => break,
+                    _ => return Err(self.error_at(&next, usage())),
+                }
+            }
+            if matrix.first().is_some_and(|first| first.len() != row.len()) {
+                return Err(self.error_at(
+                    &open_row,
+                    format!(
+                        "rows of {} need {} coefficients, not {}",
+                        text,
+                        matrix[0].len(),
+                        row.len()
+                    ),
+                ));
+            }
+            matrix.push(row);
+
+            let next = self.next_token();
+            match next.kind {
+                TokenKind::Comma => {}
+                TokenKind::RBracket => break,
+                _ => return Err(self.error_at(&next, usage())),
+            }
+        }
+
+        let end = self.next_token();
+        if !matches!(end.kind, TokenKind::Newline | TokenKind::Eof) {
+            return Err(self.error_at(&end, usage()));
+        }
+
+        debug!("let {} = {:?}", text, matrix);
+        self.matrices.insert(text, matrix);
+        Ok(())
+    }
+
+    /// Value of a constant, or of a function applied on a constant
+    fn constant_value(&self, constant: &Variable) -> Result<u32, ParserError> {
+        let value = self.constants[&constant.input()];
+        let Some(function) = constant.function() else {
+            return Ok(value);
+        };
+
+        let sbox = self.sbox(function).unwrap();
+        if value >> sbox.bits() != 0 {
+            return Err(ParserError::new(
+                self.term_pos.0,
+                self.term_pos.1,
+                format!(
+                    "{} is out of the {} bits of {}",
+                    value,
+                    sbox.bits(),
+                    function
+                ),
+            ));
+        }
+        Ok(sbox.apply(value))
+    }
+
+    /// Element of the field of the system, located at the current term
+    fn field_elem(&self, value: u32) -> Result<<GF2n as Field>::Elem, ParserError> {
+        self.field.elem(value).ok_or_else(|| {
+            ParserError::new(
+                self.term_pos.0,
+                self.term_pos.1,
+                format!("{} is out of the field {}", value, self.field),
+            )
+        })
+    }
+
+    /// Sum of two coefficients in the field of the system
+    fn field_add(&self, a: u32, b: u32) -> Result<u32, ParserError> {
+        let sum = self.field.add(self.field_elem(a)?, self.field_elem(b)?);
+        Ok(self.field.value(sum))
+    }
+
+    /// Product of two coefficients in the field of the system
+    fn field_mul(&self, a: u32, b: u32) -> Result<u32, ParserError> {
+        let product = self.field.mul(self.field_elem(a)?, self.field_elem(b)?);
+        Ok(self.field.value(product))
+    }
+
+    /* parse_system
+       Arguments   ::  - global_infos : &mut GlobalInfos
+
+       Description ::  Open the file given in global_infos and parse it
+                       to build the corresponding section
+                       In other call try to build next section
+                       and again to reach the end of file
+
+       Return      :: Ok with the section matrix
+                      OR
+                      Err with the first parsing error
+    */
+    pub fn parse_system(&mut self, global_infos: &mut GlobalInfos) -> Result<Matrix, ParserError> {
+        self.parse_section(global_infos)?;
+        self.build_matrix()
+    }
+
+    /* parse_system_diagnostics
+       Arguments   ::  - global_infos : &mut GlobalInfos
+
+       Description ::  Same as parse_system, but do not stop on the first
+                       error: the faulty line is skipped and parsing goes on
+                       to report every problem of the section
+
+       Return      :: Ok with the section matrix if no error
+                      OR
+                      Err with all parsing errors, in file order
+    */
+    pub fn parse_system_diagnostics(
+        &mut self,
+        global_infos: &mut GlobalInfos,
+    ) -> Result<Matrix, Vec<ParserError>> {
+        self.with_diagnostics(|parser| parser.parse_system(global_infos))
+    }
+
+    /* parse_sections
+       Arguments   ::  - global_infos : &mut GlobalInfos
+
+       Description ::  Parse every remaining section of the file,
+                       sections without any equation are skipped
+
+       Return      :: Ok with the (name, matrix) of each section, in file order
+                      OR
+                      Err with the first parsing error
+    */
+    pub fn parse_sections(
+        &mut self,
+        global_infos: &mut GlobalInfos,
+    ) -> Result<Vec<(String, Matrix)>, ParserError> {
+        let mut sections: Vec<(String, Matrix)> = vec![];
+
+        while !self.end_of_file {
+            self.parse_section(global_infos)?;
+            if self.matrix.is_empty() {
+                info!("Skip empty section {}", global_infos.sys_name);
+                self.expand((0, 0), format!("empty section {}", global_infos.sys_name));
+                continue;
+            }
+            sections.push((global_infos.sys_name.clone(), self.build_matrix()?));
+        }
+
+        if sections.is_empty() {
+            let (line, char_) = self.here();
+            return Err(
+                ParserError::new(line, char_, String::from("no system to parse!"))
+                    .in_file(&self.file),
+            );
+        }
+
+        Ok(sections)
+    }
+
+    /* parse_sections_diagnostics
+       Arguments   ::  - global_infos : &mut GlobalInfos
+
+       Description ::  parse_sections in the collect all diagnostics mode
+
+       Return      :: Ok with the (name, matrix) of each section if no error
+                      OR
+                      Err with all parsing errors, in file order
+    */
+    pub fn parse_sections_diagnostics(
+        &mut self,
+        global_infos: &mut GlobalInfos,
+    ) -> Result<Vec<(String, Matrix)>, Vec<ParserError>> {
+        self.with_diagnostics(|parser| parser.parse_sections(global_infos))
+    }
+
+    /* with_diagnostics
+       Arguments   ::  - parse : parsing to run
+
+       Description ::  run parse while collecting errors instead of stopping
+
+       Return      :: Ok with the parse result if no error has been collected
+                      Err with all errors either
+    */
+    fn with_diagnostics<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParserError>,
+    ) -> Result<T, Vec<ParserError>> {
+        self.collect_diagnostics = true;
+        let result = parse(self);
+        self.collect_diagnostics = false;
+
+        let mut diagnostics = std::mem::take(&mut self.diagnostics);
+        match result {
+            Ok(value) if diagnostics.is_empty() => Ok(value),
+            Ok(_) => Err(diagnostics),
+            Err(e) => {
+                diagnostics.push(e);
+                Err(diagnostics)
+            }
+        }
+    }
+
+    /* parse_section
+       Arguments   ::  - global_infos : &mut GlobalInfos
+
+       Description ::  Parse the rows of the current section into self.matrix,
+                       stop on the next section header or at the end of file
+
+       Return      :: Ok if the section has been read
+                      Err with parsing error
+    */
+    fn parse_section(&mut self, global_infos: &mut GlobalInfos) -> Result<(), ParserError> {
+        info!("Start parsing system");
+        debug!("Parser::parse_section");
+
+        // Each section is an independent system
+        self.matrix.clear();
+        self.rhs.clear();
+        self.vars_map.clear();
+
+        if self.section_name.is_none() {
+            // SEARCH FOR SECTION NAME, after the file directives
+            loop {
+                if self.skip_empty_lines() {
+                    self.end_of_file = true;
+                    let (line, char_) = self.here();
+                    return Err(
+                        ParserError::new(line, char_, String::from("File is empty!"))
+                            .in_file(&self.file),
+                    );
+                }
+
+                let line = self.here().0;
+                match self.get_section() {
+                    Ok(HeaderParse::Section) => break,
+                    Ok(HeaderParse::Directive) => {}
+                    Err(e) => {
+                        self.report(e)?;
+                        self.skip_line(line);
+                    }
+                    Ok(HeaderParse::None) => {
+                        self.end_of_file = true;
+                        let (line, char_) = self.here();
+                        return Err(ParserError::new(
+                            line,
+                            char_,
+                            String::from("No section defined! Need to start with <--my_section:>"),
+                        )
+                        .in_file(&self.file));
+                    }
+                }
+            }
+        }
+        let section_name = match &self.section_name {
+            Some(str) => str,
+            None => unreachable!(),
+        };
+
+        global_infos.sys_name = section_name.to_string();
+        global_infos.polynomial = self.field.polynomial();
+
+        // CATCH NEW SECTION => ENDING OF SYSTEM
+        if !self.parse_rows()? {
+            // END OF FILE
+            self.end_of_file = true;
+        }
+        Ok(())
+    }
+
+    /* parse_rows
+       Arguments   ::
+
+       Description ::  Parse the rows of the current source into self.matrix,
+                       up to the next section header or the end of source
+
+       Return      :: Ok with
+                       - true if a section header has been read
+                       - false at the end of source
+                      Err with parsing error
+    */
+    fn parse_rows(&mut self) -> Result<bool, ParserError> {
+        debug!("Parser::parse_rows");
+
+        loop {
+            if self.skip_empty_lines() {
+                return Ok(false);
+            }
+
+            let line = self.here().0;
+            let nb_rows = self.matrix.len();
+
+            let r_line = match self.get_section() {
+                Ok(HeaderParse::Section) => return Ok(true),
+                Ok(HeaderParse::Directive) => Ok(()),
+                // CONTINUE TO BUILD MATRIX
+                Ok(HeaderParse::None) => self.parse_line(),
+                Err(e) => Err(e),
+            };
+
+            if let Err(e) = r_line {
+                self.report(e)?;
+                // Drop the partial row and resume on next line
+                self.matrix.truncate(nb_rows);
+                self.rhs.truncate(nb_rows);
+                self.redundancy = None;
+                self.var_name = None;
+                self.group = None;
+                self.slice = None;
+                if self.skip_line(line) {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
+    /* build_matrix
+       Arguments   ::
+
+       Description ::  Build the Matrix of the rows parsed so far
+
+       Return      :: Ok with the section matrix
+                      Err if no row has been parsed
+    */
+    fn build_matrix(&self) -> Result<Matrix, ParserError> {
+        if self.matrix.is_empty() {
+            let (line, char_) = self.here();
+            return Err(
+                ParserError::new(line, char_, String::from("no system to parse!"))
+                    .in_file(&self.file),
+            );
+        }
+
+        info!("Parsing ended with success");
+        debug!("Matrix :: {:?}", self.matrix);
+
+        // Rows only hold their own terms while parsing, expand them once
+        let cols = self.vars_map.len();
+        let data = self
+            .matrix
+            .iter()
+            .map(|terms| {
+                let mut row = vec![0; cols];
+                for &(column, coef) in terms {
+                    row[column] = coef;
+                }
+                row
+            })
+            .collect();
+
+        let mut matrix = Matrix::new_from_vec(
+            data,
+            self.rhs.to_vec(),
+            self.vars_map.clone(),
+            self.field.clone(),
+        );
+        let roles = self
+            .vars_map
+            .keys()
+            .filter_map(|var| {
+                let declared = self
+                    .roles
+                    .iter()
+                    .rev()
+                    .find(|(p, _)| var.input().matches(p));
+                declared.map(|&(_, role)| (var.input(), role))
+            })
+            .collect();
+        matrix.set_roles(roles);
+
+        let functions: HashSet<&str> = self
+            .vars_map
+            .keys()
+            .filter_map(Variable::function)
+            .collect();
+        matrix.set_sboxes(
+            functions
+                .into_iter()
+                .filter_map(|name| Some((name.to_string(), self.sbox(name)?)))
+                .collect(),
+        );
+
+        Ok(matrix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variable::var;
+
+    #[test]
+    fn empty_system() {
+        let mut global_infos = GlobalInfos::new(String::from("test/empty.eqs"));
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
+
+        assert!(parser_mod.parse_system(&mut global_infos).is_err());
+    }
+
+    #[test]
+    fn only_commentary() {
+        let mut global_infos = GlobalInfos::new(String::from("test/only_comments.eqs"));
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
+
+        assert!(parser_mod.parse_system(&mut global_infos).is_err());
+    }
+
+    #[test]
+    fn valid_system() {
+        let mut global_infos = GlobalInfos::new(String::from("test/valid.eqs"));
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
+
+        assert!(parser_mod.parse_system(&mut global_infos).is_ok());
+    }
+
+    #[test]
+    fn missing_file() {
+        let global_infos = GlobalInfos::new(String::from("test/does_not_exist.eqs"));
+
+        let err = Parser::new(&global_infos).err().unwrap();
+
+        assert_eq!((err.line, err.char_), (0, 0));
+    }
+
+    #[test]
+    fn from_reader() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let system: &[u8] = b"--system:\nW_0[0,0] + 02*K_1[0,0]\n";
+        let mut parser_mod = Parser::from_reader(system, &global_infos).unwrap();
+
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
+
+        assert_eq!(global_infos.sys_name, "system");
+        assert_eq!(mtr.get_row(0), [1.into(), 2.into()]);
+    }
+
+    #[test]
+    fn from_reader_invalid_utf8() {
+        let global_infos = GlobalInfos::new(String::new());
+        let system: &[u8] = b"--system:\nW_0[0,0] + \xff\n";
+
+        assert!(Parser::from_reader(system, &global_infos).is_err());
+    }
+
+    #[test]
+    fn from_reader_invalid_field() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        global_infos.polynomial = 0x11;
+        let system: &[u8] = b"--system:\nW_0[0,0]\n";
+
+        let err = Parser::from_reader(system, &global_infos).err().unwrap();
+        assert!(err.msg.contains("irreducible"));
+        assert_eq!(
+            Parser::from_string("--system:\nW_0[0,0]\n", &global_infos).field,
+            GF2n::aes()
+        );
+    }
+
+    #[test]
+    fn long_line() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let terms = (0..30)
+            .map(|i| format!("X_0[{},{}]", i / 4, i % 4))
+            .collect::<Vec<_>>();
+        let system = format!("--system:\n{}\n", terms.join(" + "));
+        let mut parser_mod = Parser::from_string(&system, &global_infos);
+
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
+
+        assert_eq!(mtr.get_row(0), vec![1.into(); 30]);
+    }
+
+    #[test]
+    fn frequent_variable() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let rows = (0..15)
+            .map(|i| format!("K_0[0,0] + {}*X_0[0,{}]", i + 1, i))
+            .collect::<Vec<_>>();
+        let system = format!("--system:\n{}\n", rows.join("\n"));
+        let mut parser_mod = Parser::from_string(&system, &global_infos);
+
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
+
+        assert_eq!(mtr.get_row_number(), 15);
+        assert_eq!(mtr.get_all_variables().len(), 16);
+        // K_0[0,0] is the first variable met
+        assert_eq!(mtr.get_column(0), vec![1.into(); 15]);
+    }
+
+    #[test]
+    fn large_system() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut system = String::from("--system:\n");
+        for row in 0..20_000 {
+            let terms = (0..10)
+                .map(|i| format!("02*S(X_{}[{},{}])", i, row % 4, (row + i) % 4))
+                .collect::<Vec<_>>();
+            system.push_str(&terms.join(" + "));
+            system.push_str(" # pair #\n");
+        }
+        let mut parser_mod = Parser::from_string(&system, &global_infos);
+
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
+
+        assert_eq!(mtr.get_row_number(), 20_000);
+        assert_eq!(mtr.get_all_variables().len(), 40);
+    }
+
+    #[test]
+    fn error_03_diagnostics() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             W_0[0,a] + K_1[0,0]\n\
+             W_0[0,0 + K_1[0,0]\n\
+             S(02) + K_1[0,0]\n\
+             S(X_0[0,0] + K_1[0,0]\n\
+             W_0[0,0] - K_1[0,0]\n\
+             W_0[0,0] + K_1[0,0] -- end:\n\
+             W_0 [0, 0] + K_1[0,0]\n",
+            &global_infos,
+        );
+
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+
+        assert_eq!(
+            errs.iter().map(|e| (e.line, e.char_)).collect::<Vec<_>>(),
+            [(2, 7), (3, 11), (4, 3), (5, 12), (6, 10), (7, 21)]
+        );
+    }
+
+    #[test]
+    fn variables_00() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             S(X_1[0,0]) + X_10[0, 0] + KC_0\n\
+             S(S(X_1[0,0])) + X_1[0,0]\n",
+            &global_infos,
+        );
+
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+        assert_eq!(
+            errs.iter().map(|e| (e.line, e.char_)).collect::<Vec<_>>(),
+            [(3, 3)]
+        );
+
+        let mut vars: Vec<_> = parser_mod.vars_map.iter().collect();
+        vars.sort_by_key(|(_, col)| *col);
+        assert_eq!(
+            vars.into_iter().map(|(v, _)| v.clone()).collect::<Vec<_>>(),
+            [var("S(X_1[0,0])"), var("X_10[0,0]"), var("KC_0")]
+        );
+    }
+
+    #[test]
+    fn variables_shared() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string("--system:\nX + Y\n", &global_infos);
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
+
+        // The matrix keeps the names of the parser
+        let parsed = parser_mod.vars_map.shared(&var("X")).unwrap();
+        let kept = mtr.get_vars_map().shared(&var("X")).unwrap();
+        assert!(std::sync::Arc::ptr_eq(&parsed, &kept));
+    }
+
+    #[test]
+    fn error_00() {
+        let mut global_infos = GlobalInfos::new(String::from("test/error_00.eqs"));
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
+
+        let err = parser_mod.parse_system(&mut global_infos).unwrap_err();
+
+        assert_eq!((err.line, err.char_), (4, 3));
+        assert_eq!(err.file.as_deref(), Some("test/error_00.eqs"));
+    }
+
+    #[test]
+    fn error_00_diagnostics() {
+        let mut global_infos = GlobalInfos::new(String::from("test/error_00.eqs"));
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
+
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+
+        assert_eq!(
+            errs.iter().map(|e| (e.line, e.char_)).collect::<Vec<_>>(),
+            [(4, 3), (5, 16), (6, 12)]
+        );
+    }
+
+    #[test]
+    fn simple_00_diagnostics() {
+        let mut global_infos = GlobalInfos::new(String::from("test/simple_00.eqs"));
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
+
+        let mtr = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap();
+
+        assert_eq!(mtr.get_row_number(), 2);
+    }
+
+    #[test]
+    fn sections_00() {
+        let mut global_infos = GlobalInfos::new(String::from("test/sections_00.eqs"));
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
+
+        let sections = parser_mod.parse_sections(&mut global_infos).unwrap();
+
+        assert_eq!(
+            sections.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>(),
+            ["round_1", "round_2"]
+        );
+        assert_eq!(sections[0].1.get_row_number(), 2);
+        assert_eq!(sections[0].1.get_all_variables().len(), 4);
+        assert_eq!(sections[1].1.get_row_number(), 1);
+        assert_eq!(sections[1].1.get_row(0), [1.into(), 2.into()]);
+    }
+
+    #[test]
+    fn sections_00_one_by_one() {
+        let mut global_infos = GlobalInfos::new(String::from("test/sections_00.eqs"));
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
+
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
+        assert_eq!(global_infos.sys_name, "round_1");
+        assert_eq!(mtr.get_row_number(), 2);
+
+        // Empty section
+        assert!(parser_mod.parse_system(&mut global_infos).is_err());
+        assert_eq!(global_infos.sys_name, "empty");
+
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
+        assert_eq!(global_infos.sys_name, "round_2");
+        assert_eq!(mtr.get_row(0), [1.into(), 2.into()]);
+    }
+
+    #[test]
+    fn error_01_diagnostics() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             W_0[0,0] + 256*K_1[0,0]\n\
+             W_0[0,1] + 0x1ff * K_1[0,1]\n\
+             W_0[0,2] + 0xg * K_1[0,2]\n\
+             ",
+            &global_infos,
+        );
+
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+
+        assert_eq!(
+            errs.iter().map(|e| (e.line, e.char_)).collect::<Vec<_>>(),
+            [(2, 12), (3, 12), (4, 12), (5, 1)]
+        );
+    }
+
+    #[test]
+    fn hex_00() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             W_0[0,0] + 0x02*S(X_0[0,0]) + 0b11*S(X_0[1,1]) + 0x1b\n\
+             ",
+            &global_infos,
+        );
+
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
+
+        assert_eq!(mtr.get_row(0), [1.into(), 2.into(), 3.into()]);
+        assert_eq!(mtr.get_rhs(0), 0x1b.into());
+    }
+
+    #[test]
+    fn hex_01() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "-- radix: 16\n\
+             -- system:\n\
+             W_0[0,0] + 02*S(X_0[0,0]) + 03*S(X_0[1,1]) + 1b\n\
+             10*W_0[0,0] + 0x10*S(X_0[0,0]) + ff*S(X_0[1,1])\n\
+             ",
+            &global_infos,
+        );
+
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
-        Ok(EndOfTermParse::File)
+        assert_eq!(global_infos.sys_name, "system");
+        assert_eq!(mtr.get_row_number(), 2);
+        assert_eq!(mtr.get_row(0), [1.into(), 2.into(), 3.into()]);
+        assert_eq!(mtr.get_row(1), [0x10.into(), 0x10.into(), 0xff.into()]);
+        assert_eq!(mtr.get_rhs(0), 0x1b.into());
+        assert_eq!(mtr.get_rhs(1), 0.into());
     }
 
-    /* get_vec_ndx
-       Arguments   ::
+    #[test]
+    fn hex_02() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--radix: 16\n\
+             --system:\n\
+             0b*X + 0e*Y + c6*Z + S(Y)*a\n\
+             const c0 = 1\n\
+             c6*X + 02*c0 + b\n\
+             ",
+            &global_infos,
+        );
 
-       Description ::  get the index in which column self.var_name
-                       need to be stored
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
-       Return      :: Some with the valid index
-                      None if nobody in self.var_name and self_redudancy
-    */
-    fn get_vec_ndx(&mut self) -> Option<usize> {
-        debug!("Parser::get_vec_index");
+        assert_eq!(
+            mtr.get_row(0),
+            [0x0b.into(), 0x0e.into(), 0xc6.into(), 0x0a.into(), 0.into()]
+        );
+        assert_eq!(
+            mtr.get_row(1),
+            [0xc6.into(), 0.into(), 0.into(), 0.into(), 1.into()]
+        );
+        assert_eq!(mtr.get_rhs(1), 2.into());
+
+        // 0b is not a binary prefix in radix 16
+        let mut parser_mod = Parser::from_string("--radix: 16\n--system:\n0b11*X\n", &global_infos);
+        let err = parser_mod.parse_system(&mut global_infos).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("coefficient 0b11 is out of the field"));
+    }
 
-        // See if variable in term
-        let str: &str = match &self.var_name {
-            Some(s) => s,
-            None => {
-                // Empty term
-                self.redundancy?;
-                // Only known value :: KV
-                "KV"
-            }
-        };
+    #[test]
+    fn error_02_diagnostics() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             a + b = c = d\n\
+             = a\n\
+             const 2x = 3\n\
+             const c0 = 0x1ff\n\
+             const a = 1\n\
+             a + b = c\n\
+             ",
+            &global_infos,
+        );
 
-        // Search in map if variable exist
-        match self.vars_map.get(str) {
-            Some(&index) => Some(index),
-            None => {
-                // Create new index
-                let index = self.vars_map.len();
-                debug!("{} at index {}", str, index);
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+
+        assert_eq!(
+            errs.iter().map(|e| e.line).collect::<Vec<_>>(),
+            [2, 3, 4, 5, 6]
+        );
+    }
 
-                self.vars_map.insert(str.to_string(), index);
+    #[test]
+    fn rhs_00() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             const c0 = 0x63\n\
+             const c1 = 2 # comment\n\
+             W_0[0,0] + K_1[0,0] = C[0,0] + c0\n\
+             02*S(X_0[0,0]) = 03 * c0 + c1 + 1\n\
+             X_0[0,0] = 5\n\
+             ",
+            &global_infos,
+        );
 
-                let line = self.matrix.len() - 1;
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
-                // extend matrix
-                for i in 0..=line {
-                    self.matrix[i].push(0);
-                }
+        assert_eq!(mtr.get_row_number(), 3);
+        assert_eq!(
+            mtr.get_row(0),
+            [1.into(), 1.into(), 1.into(), 0.into(), 0.into()]
+        );
+        assert_eq!(
+            mtr.get_row(1),
+            [0.into(), 0.into(), 0.into(), 2.into(), 0.into()]
+        );
+        assert_eq!(
+            mtr.get_row(2),
+            [0.into(), 0.into(), 0.into(), 0.into(), 1.into()]
+        );
+        assert_eq!(mtr.get_rhs(0), 0x63.into());
+        // 03 * 0x63 + 2 + 1
+        assert_eq!(mtr.get_rhs(1), 0xa6.into());
+        assert_eq!(mtr.get_rhs(2), 5.into());
+    }
 
-                self.matrix_count.push(1);
+    #[test]
+    fn for_00() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             for i in 0..4, j in 0..4: W_0[i,j] + K_1[i,j] + C[i,j]\n",
+            &global_infos,
+        );
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
-                Some(index)
+        let mut rows = String::from("--system:\n");
+        for i in 0..4 {
+            for j in 0..4 {
+                rows += &format!("W_0[{i},{j}] + K_1[{i},{j}] + C[{i},{j}]\n");
             }
         }
-    }
-
-    /* store_term
-       Arguments   ::
+        let mut expanded = Parser::from_string(&rows, &global_infos);
+        let expected = expanded.parse_system(&mut global_infos).unwrap();
 
-       Description :: store the term in matrix
+        assert_eq!(parser_mod.vars_map, expanded.vars_map);
+        assert_eq!(mtr.get_row_number(), 16);
+        for row in 0..16 {
+            assert_eq!(mtr.get_row(row), expected.get_row(row));
+        }
+    }
 
-       Return      :: Ok
-                      Err either
-    */
-    fn store_term(&mut self) -> Result<bool, ParserError> {
-        debug!("Parser::store_term");
+    #[test]
+    fn for_shift_rows() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             for j in 0..4: W_0[0,j] + 02*S(X_0[0,j]) + 03*S(X_0[1,(j+1)%4]) + S(X_0[3,(j-1)%4])\n\
+             for i in 1..2*2 - 1, j in 0..2: Y[i, j/2, 10*i + (j)] = 1\n",
+            &global_infos,
+        );
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
-        if self.redundancy.is_none() && self.var_name.is_none() {
-            return Ok(false);
+        assert_eq!(mtr.get_row_number(), 8);
+        let row_3: Vec<_> = mtr.get_row(3).iter().map(|c| c.get_value()).collect();
+        let column = |name: &str| parser_mod.vars_map[&var(name)];
+        assert_eq!(row_3[column("W_0[0,3]")], 1);
+        assert_eq!(row_3[column("S(X_0[0,3])")], 2);
+        assert_eq!(row_3[column("S(X_0[1,0])")], 3);
+        assert_eq!(row_3[column("S(X_0[3,2])")], 1);
+        assert_eq!(row_3.iter().filter(|&&c| c != 0).count(), 4);
+        for name in ["Y[1,0,10]", "Y[1,0,11]", "Y[2,0,20]", "Y[2,0,21]"] {
+            assert!(parser_mod.vars_map.contains_key(&var(name)), "{}", name);
         }
+        assert_eq!(mtr.get_rhs(7), 1.into());
+    }
 
-        if let Some(index) = Parser::get_vec_ndx(self) {
-            let rdd = self.redundancy.unwrap_or(1);
-
-            if self.matrix_count[index] == MAX_NB_MATRIX {
-                let name = match &self.var_name {
-                    Some(n) => n.clone(),
-                    None => String::from("KV"),
-                };
+    #[test]
+    fn for_diagnostics() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             for i in 0..4 W_0[i]\n\
+             for i in 0..4: W_0[i - 1]\n\
+             for i in 0..4: W_0[j]\n\
+             for i in 0..4, i in 0..4: W_0[i]\n\
+             for i in 0..4: W_0[i % (i - i)]\n\
+             for i in 0..4: i * W_0[i]\n\
+             for i in 0..4:\n\
+             for i in 0..4: W_0[i] + K_0[i]\n",
+            &global_infos,
+        );
 
-                return Err(ParserError::new(
-                    self.reader.line,
-                    self.reader.char_,
-                    format!("term {}*{} reach this maximum occurence", rdd, name),
-                ));
-            }
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+        assert_eq!(
+            errs.iter().map(|e| (e.line, e.char_)).collect::<Vec<_>>(),
+            [
+                (2, 15),
+                (3, 20),
+                (4, 20),
+                (5, 16),
+                (6, 22),
+                (7, 16),
+                (8, 15)
+            ]
+        );
+        // only the valid loop is kept, the others do not leave partial rows
+        assert_eq!(parser_mod.matrix.len(), 4);
+        assert!(parser_mod.loop_indices.is_empty());
+    }
 
-            let line = self.matrix.len() - 1;
-            self.matrix[line][index] = rdd; // SET REDUNDANCY ON MATRIX
-            self.matrix_count[index] += 1;
+    #[test]
+    fn include_00() {
+        let mut global_infos = GlobalInfos::new(String::from("test/include_00.eqs"));
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
+
+        let sections = parser_mod.parse_sections(&mut global_infos).unwrap();
+        assert_eq!(sections.len(), 2);
+        let mtr = &sections[0].1;
+        assert_eq!(mtr.get_row_number(), 6);
+
+        let vars = mtr.get_all_variables();
+        for name in [
+            "W_0[0,0]",
+            "S(X_0[1,0])",
+            "K_1[0,0]",
+            "X_1[1,0]",
+            "S(X_1[0,0])",
+            "K_2[1,0]",
+            "X_2[1,0]",
+            "S(K_0[1,0])",
+        ] {
+            assert!(vars.contains(&var(name)), "{}", name);
         }
+        assert!(!vars.iter().any(|v| v.to_string().contains("_r")));
+        assert_eq!(vars.len(), 20);
+        assert_eq!(
+            mtr.get_row(5).iter().filter(|c| c.get_value() != 0).count(),
+            2
+        );
+    }
 
-        // Reset term after storing
-        self.redundancy = None;
-        self.var_name = None;
+    #[test]
+    fn include_cycle() {
+        let mut global_infos = GlobalInfos::new(String::from("test/include_cycle.eqs"));
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
+
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+
+        assert_eq!(errs.len(), 1);
+        assert_eq!(errs[0].file.as_deref(), Some("test/include_cycle_b.eqs"));
+        assert_eq!((errs[0].line, errs[0].char_), (2, 1));
+        assert!(errs[0].msg.starts_with("include cycle"));
+        assert_eq!(parser_mod.matrix.len(), 2);
+    }
 
-        Ok(true)
+    #[test]
+    fn include_diagnostics() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--include: test/include_round.eqs\n--system:\n",
+            &global_infos,
+        );
+        let err = parser_mod.parse_system(&mut global_infos).unwrap_err();
+        assert_eq!((err.file, err.line, err.char_), (None, 1, 1));
+
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             --include: test/error_00.eqs\n\
+             --include: test/missing.eqs\n\
+             --use: nothing\n\
+             --use: radix\n\
+             --include: test/include_round.eqs (r=1, s)\n\
+             --use: system\n\
+             --include: test/include_round.eqs (r=1, s=2)\n",
+            &global_infos,
+        );
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+        assert_eq!(
+            errs.iter()
+                .map(|e| (e.file.as_deref(), e.line))
+                .collect::<Vec<_>>(),
+            [
+                // the included file is checked as a whole
+                (Some("test/error_00.eqs"), 1),
+                (Some("test/error_00.eqs"), 4),
+                (Some("test/error_00.eqs"), 5),
+                (Some("test/error_00.eqs"), 6),
+                (None, 3),
+                (None, 4),
+                (None, 5),
+                (None, 6),
+                (None, 7)
+            ]
+        );
+        // valid rows of error_00.eqs and of the round
+        assert_eq!(parser_mod.matrix.len(), 4);
+        assert!(parser_mod.vars_map.contains_key(&var("X_2[1,0]")));
     }
 
-    /* parse_line
-       Arguments   ::
+    #[test]
+    fn roles_00() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--plaintext: P\n\
+             --ciphertext: C\n\
+             --key: K\n\
+             --target: K_0[0,0]  K_0[1,0]\n\
+             --system:\n\
+             P[0,0] + K_0[0,0] + X[0,0]\n\
+             S(X[0,0]) + K_1[0,0] + C[0,0]\n\
+             S(K_0[1,0]) + Y\n\
+             --key: S(K)\n\
+             --state: X[\n",
+            &global_infos,
+        );
 
-       Description ::  parse line of the input file
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+        assert_eq!(
+            errs.iter().map(|e| (e.line, e.char_)).collect::<Vec<_>>(),
+            [(9, 1), (10, 1)]
+        );
 
-       Return      ::  Ok with possible EndOfLineParse
-                       Err either
-    */
-    fn parse_line(&mut self) -> Result<EndOfLineParse, ParserError> {
-        debug!("Parser::parse_line");
+        let mtr = parser_mod.build_matrix().unwrap();
+        assert_eq!(mtr.role(&var("P[0,0]")), Some(Role::Plaintext));
+        assert_eq!(mtr.role(&var("C[0,0]")), Some(Role::Ciphertext));
+        assert_eq!(mtr.role(&var("K_1[0,0]")), Some(Role::Key));
+        assert_eq!(mtr.role(&var("S(K_0[1,0])")), Some(Role::Target));
+        assert_eq!(mtr.role(&var("X[0,0]")), None);
+        assert_eq!(mtr.role(&var("Y")), None);
+
+        let mut targets = mtr.get_target_variables();
+        targets.sort();
+        assert_eq!(targets, [var("K_0[0,0]"), var("K_0[1,0]")]);
+    }
 
-        let mut cmpt_iter: u8 = 0;
-        let mut add_term: bool = false;
-        self.matrix.push(vec![0; self.vars_map.len()]);
+    #[test]
+    fn sbox_00() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        // T(x) = x + 3 mod 256
+        let table: Vec<String> = (0..256).map(|x| format!("{:x}", (x + 3) % 256)).collect();
+        let mut parser_mod = Parser::from_string(
+            &format!(
+                "--radix: 16\n\
+                 --sbox T = [{}]\n\
+                 --sbox Tinv = inverse(T)\n\
+                 --system:\n\
+                 T(X[0]) + X[0] + Tinv(X[0]) + Y\n\
+                 S(Y) + Y\n\
+                 const c = 2\n\
+                 Tinv(c) + 02*S(c) + Y\n",
+                table.join(", ")
+            ),
+            &global_infos,
+        );
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
-        loop {
-            if cmpt_iter == MAX_NB_TERM {
-                // CHECK THE MAX NUMBER of term in ONE line
-                return Err(ParserError::new(
-                    self.reader.line,
-                    self.reader.char_,
-                    String::from("too many terms in line"),
-                ));
-            }
+        assert_eq!(mtr.get_sbox("T").unwrap().apply(0), 3);
+        assert_eq!(mtr.get_sbox("Tinv").unwrap().apply(3), 0);
+        assert_eq!(mtr.get_sbox("S"), Some(&Sbox::aes()));
+        assert_eq!(mtr.get_sbox("U"), None);
+        // 0xff + 02 * 0x77
+        assert_eq!(mtr.get_rhs(2), 0x11.into());
+        assert_eq!(
+            mtr.get_row(2).iter().filter(|c| c.get_value() != 0).count(),
+            1
+        );
 
-            let r_es: EndOfTermParse = self.get_term().expect("Error while getting term");
-            add_term = self.store_term().expect("Error while storing term");
+        let mut pairs = crate::matrix::get_variable_if_sboxed(&mtr.get_all_variables());
+        pairs.sort();
+        assert_eq!(
+            pairs,
+            [
+                (var("X[0]"), var("T(X[0])")),
+                (var("X[0]"), var("Tinv(X[0])")),
+                (var("Y"), var("S(Y)"))
+            ]
+        );
+    }
 
-            match r_es {
-                EndOfTermParse::File => {
-                    if !add_term {
-                        self.matrix.pop();
-                    }
-                    return Ok(EndOfLineParse::File);
-                }
-                EndOfTermParse::Line => {
-                    if !add_term {
-                        self.matrix.pop();
-                    }
-                    return Ok(EndOfLineParse::Line);
-                }
-                EndOfTermParse::Term => {}
-            }
+    #[test]
+    fn sbox_field() {
+        // S is not the AES S-box out of GF(2^8)
+        for system in [
+            "--field: GF(2^4) / 0x13\n--system:\nconst c = 3\nS(c) + a\n",
+            "--field: GF(2^4) / 0x13\n--system:\nS(a) + b\n",
+        ] {
+            let mut global_infos = GlobalInfos::new(String::new());
+            let err = Parser::from_string(system, &global_infos)
+                .parse_system(&mut global_infos)
+                .unwrap_err();
+            assert!(err.msg.contains("unknown function S"), "{}", err);
+        }
 
-            cmpt_iter += 1;
+        // declared on the 4 bits of the field
+        let mut global_infos = GlobalInfos::new(String::new());
+        let table: Vec<String> = (0..16).map(|x| format!("{}", (x + 1) % 16)).collect();
+        let system = format!(
+            "--field: GF(2^4) / 0x13\n--sbox S = [{}]\n--system:\nconst c = 3\nS(c) + a\n",
+            table.join(", ")
+        );
+        let mtr = Parser::from_string(&system, &global_infos)
+            .parse_system(&mut global_infos)
+            .unwrap();
+        assert_eq!(mtr.get_rhs(0), 4.into());
+
+        // sbox and field of other sizes
+        let table: Vec<String> = (0..256).map(|x| x.to_string()).collect();
+        for (system, bits) in [
+            (
+                String::from("--field: GF(2^4) / 0x13\n--sbox T = [0, 1, 3, 2]\n"),
+                2,
+            ),
+            (
+                format!(
+                    "--sbox T = [{}]\n--field: GF(2^4) / 0x13\n",
+                    table.join(", ")
+                ),
+                8,
+            ),
+        ] {
+            let mut global_infos = GlobalInfos::new(String::new());
+            let err = Parser::from_string(&format!("{}--system:\nT(a)\n", system), &global_infos)
+                .parse_system(&mut global_infos)
+                .unwrap_err();
+            assert_eq!(err.line, 2);
+            assert!(
+                err.msg.contains(&format!("sbox T is on {} bits", bits)),
+                "{}",
+                err
+            );
         }
     }
 
-    /* parse_system
-       Arguments   ::  - global_infos : &mut GlobalInfos
+    #[test]
+    fn sbox_diagnostics() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--field: GF(2^1) / 0x3\n\
+             --sbox T = [1, 2]\n\
+             --sbox U = inverse(V)\n\
+             --sbox W = [0, 0]\n\
+             --sbox Winv = inverse(W)\n\
+             --sbox = [0, 1]\n\
+             --sbox X = [0, 0x100]\n\
+             --system:\n\
+             --sbox W = [1, 0]\n\
+             F(X[0]) + X[0]\n\
+             W(X[0]) + X[0]\n\
+             const c = 2\n\
+             W(c) + X[0]\n",
+            &global_infos,
+        );
 
-       Description ::  Open the file given in global_infos and parse it
-                       to build the corresponding section
-                       In other call try to build next section
-                       and again to reach the end of file
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+        assert_eq!(
+            errs.iter().map(|e| e.line).collect::<Vec<_>>(),
+            [2, 3, 5, 6, 7, 9, 10, 12]
+        );
+        assert_eq!(parser_mod.matrix.len(), 2);
+    }
 
-       Return      :: Ok with the section matrix
-                      OR
-                      Err with parsing error
-    */
-    pub fn parse_system(&mut self, global_infos: &mut GlobalInfos) -> Result<Matrix, ParserError> {
-        info!("Start parsing system");
-        debug!("Parser::parse_system");
+    #[test]
+    fn repeated_terms() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             X + 03*X + Y\n\
+             02*S(Y) + Z + 03*S(Y) = Y\n\
+             X + Y + X + Y\n\
+             Z + 02*Z + 03*Z = 1\n",
+            &global_infos,
+        );
 
-        if self.skip_whitespaces() {
-            // PARSE ALL WHITESPACES
-            return Err(ParserError::new(
-                self.reader.line,
-                self.reader.char_,
-                String::from("File is empty!"),
-            ));
-        }
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+        assert_eq!(
+            errs.iter().map(|e| (e.line, e.char_)).collect::<Vec<_>>(),
+            [(5, 1)]
+        );
+        let warnings = parser_mod.take_warnings();
+        assert_eq!(
+            warnings
+                .iter()
+                .map(|w| (w.line, w.char_))
+                .collect::<Vec<_>>(),
+            [(2, 8), (3, 18), (4, 9), (4, 13), (4, 1), (5, 8), (5, 15)]
+        );
+        assert!(warnings[0]
+            .to_string()
+            .starts_with("ParserWarning at line 2"));
+        assert!(parser_mod.take_warnings().is_empty());
+
+        let mtr = parser_mod.build_matrix().unwrap();
+        let x = mtr.get_vars_map()[&var("X")];
+        let y = mtr.get_vars_map()[&var("Y")];
+        let sy = mtr.get_vars_map()[&var("S(Y)")];
+        // 1 + 3, 2 + 3 in GF(2^8)
+        assert_eq!(mtr.get_row_number(), 2);
+        assert_eq!(mtr.get_row(0)[x], 2.into());
+        assert_eq!(mtr.get_row(1)[sy], 1.into());
+        assert_eq!(mtr.get_row(1)[y], 1.into());
+    }
 
-        if self.section_name.is_none() // SEARCH FOR SECTION NAME
-            && !self.get_section().expect("Error while getting section")
-        {
-            return Err(ParserError::new(
-                self.reader.line,
-                self.reader.char_,
-                String::from("No section defined! Need to start with <--my_section:>"),
-            ));
-        }
-        let section_name = match &self.section_name {
-            Some(str) => str,
-            None => unreachable!(),
-        };
+    #[test]
+    fn parenthesis_00() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             W = 02*(S(a)) + 03*(S(b))\n\
+             02*(S(a) + S(b)) + (K_0[0,0] + K_1[0,0])\n\
+             03*(a + 02*(b + 1)) + (b)*04 = a\n\
+             const c = 3\n\
+             (c + a) + a + (b + b) = c\n",
+            &global_infos,
+        );
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
+        let column = |name: &str| mtr.get_vars_map()[&var(name)];
 
-        global_infos.sys_name = section_name.to_string();
+        assert_eq!(mtr.get_row_number(), 3);
+        assert_eq!(mtr.get_row(0)[column("W")], 1.into());
+        assert_eq!(mtr.get_row(0)[column("S(a)")], 2.into());
+        assert_eq!(mtr.get_row(0)[column("S(b)")], 3.into());
+
+        assert_eq!(mtr.get_row(1)[column("S(a)")], 2.into());
+        assert_eq!(mtr.get_row(1)[column("S(b)")], 2.into());
+        assert_eq!(mtr.get_row(1)[column("K_0[0,0]")], 1.into());
+        assert_eq!(mtr.get_row(1)[column("K_1[0,0]")], 1.into());
+
+        // 03*a + 06*b + 06 + 04*b + a, in GF(2^8)
+        assert_eq!(mtr.get_row(2)[column("a")], 2.into());
+        assert_eq!(mtr.get_row(2)[column("b")], 2.into());
+        assert_eq!(mtr.get_rhs(2), 6.into());
+        // a on both sides, a repeated after its sum, b in its sum,
+        // then the line cancels out
+        assert_eq!(
+            parser_mod
+                .take_warnings()
+                .iter()
+                .map(|w| (w.line, w.char_))
+                .collect::<Vec<_>>(),
+            [(4, 32), (6, 11), (6, 20), (6, 1)]
+        );
+    }
 
-        loop {
-            if self.skip_whitespaces() {
-                break;
-            }
+    #[test]
+    fn parenthesis_diagnostics() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             02*(a + b\n\
+             a*(b + c)\n\
+             02*() + a\n\
+             (a + b)(c)\n\
+             a + b) + c\n\
+             (a + (b + a)) = 1\n\
+             (a + b) + c\n",
+            &global_infos,
+        );
 
-            if self
-                .get_section() // CATCH NEW SECTION => ENDING OF SYSTEM
-                .expect("Error while getting section")
-            {
-                if self.matrix.is_empty() {
-                    return Err(ParserError::new(
-                        self.reader.line,
-                        self.reader.char_,
-                        String::from("no system to parse!"),
-                    ));
-                }
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+        assert_eq!(
+            errs.iter().map(|e| (e.line, e.char_)).collect::<Vec<_>>(),
+            [(2, 4), (3, 3), (4, 4), (5, 8), (6, 6)]
+        );
+        // a cancels out of the nested sums
+        assert_eq!(parser_mod.matrix.len(), 2);
+        assert_eq!(parser_mod.matrix[0].len(), 1);
+        assert_eq!(parser_mod.rhs, [1, 0]);
+    }
 
-                info!("Parsing ended with success");
-                debug!("Matrix :: {:?}", self.matrix);
-                let matrix = &self.matrix;
-                let vars_map = &self.vars_map;
-                return Ok(Matrix::new_from_vec(
-                    matrix.to_vec(),
-                    vars_map.clone(),
-                    global_infos.polynomial,
-                ));
-            } else {
-                // CONTINUE TO BUILD MATRIX
-                match self.parse_line().expect("Error while processing line") {
-                    EndOfLineParse::File => {
-                        break;
-                    }
-                    EndOfLineParse::Line | EndOfLineParse::Comment => {}
-                }
-            }
-        }
+    #[test]
+    fn field_00() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--field: GF(2^4) / 0x13\n\
+             --system:\n\
+             --field: GF(2 ^ 4) / 0b10011\n\
+             09*(02*a + b) + 0xf*c\n",
+            &global_infos,
+        );
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
+        let column = |name: &str| mtr.get_vars_map()[&var(name)];
+
+        assert_eq!(global_infos.polynomial, 0x13);
+        assert_eq!(mtr.field().polynomial(), 0x13);
+        // x^3 + 1 times x is x^4 + x = 1
+        assert_eq!(mtr.get_row(0)[column("a")], 1.into());
+        assert_eq!(mtr.get_row(0)[column("b")], 9.into());
+        assert_eq!(mtr.get_row(0)[column("c")], 0xf.into());
+    }
 
-        // END OF FILE
-        if self.matrix.is_empty() {
-            return Err(ParserError::new(
-                self.reader.line,
-                self.reader.char_,
-                String::from("no system to parse!"),
-            ));
-        }
+    #[test]
+    fn field_diagnostics() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--field: GF(2^4)\n\
+             --field: GF(2^17) / 0x20009\n\
+             --field: GF(2^4) / 0x11b\n\
+             --field: GF(2^4) / 0x11\n\
+             --field: GF(2^4) / 0x13\n\
+             --system:\n\
+             0x10*a + b\n\
+             --field: GF(2^8) / 0x11b\n\
+             a + b\n",
+            &global_infos,
+        );
 
-        info!("Parsing ended with success");
-        debug!("Matrix :: {:?}", self.matrix);
-        let matrix = &self.matrix;
-        let vars_map = &self.vars_map;
-        Ok(Matrix::new_from_vec(
-            matrix.to_vec(),
-            vars_map.clone(),
-            global_infos.polynomial,
-        ))
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+        assert_eq!(
+            errs.iter().map(|e| e.line).collect::<Vec<_>>(),
+            [1, 2, 3, 4, 7, 8]
+        );
+        assert!(errs[3].msg.contains("irreducible"));
+        assert_eq!(parser_mod.field.polynomial(), 0x13);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
-    fn empty_system() {
-        let mut global_infos = GlobalInfos::new(String::from("test/empty.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+    fn layer_00() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             let M = [[1, 2], [0, 3]]\n\
+             let N = [[1, 1], [1, 0]]\n\
+             Y[*] = M * S(X[*,1-*]) + 02*N*(A[*] + 1)\n\
+             for j in 0..2: Z[*,j] + K[j] = M * Z[j,*]\n",
+            &global_infos,
+        );
+        let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
+        let column = |name: &str| mtr.get_vars_map()[&var(name)];
+        let terms = |row: usize| {
+            let mut terms: Vec<(String, u16)> = mtr
+                .get_vars_map()
+                .iter()
+                .map(|(var, &j)| (var.to_string(), mtr.get_row(row)[j].get_value()))
+                .filter(|&(_, coef)| coef != 0)
+                .collect();
+            terms.sort();
+            terms
+        };
 
-        assert!(parser_mod.parse_system(&mut global_infos).is_err());
+        assert_eq!(mtr.get_row_number(), 6);
+        // Y[0] + S(X[0,1]) + 02*S(X[1,0]) + 02*A[0] + 02*A[1], rhs 02 + 02
+        assert_eq!(
+            terms(0),
+            [
+                (String::from("A[0]"), 2),
+                (String::from("A[1]"), 2),
+                (String::from("S(X[0,1])"), 1),
+                (String::from("S(X[1,0])"), 2),
+                (String::from("Y[0]"), 1)
+            ]
+        );
+        assert_eq!(mtr.get_rhs(0), 0.into());
+        // Y[1] + 03*S(X[1,0]) + 02*A[0] = 02
+        assert_eq!(mtr.get_row(1)[column("S(X[1,0])")], 3.into());
+        assert_eq!(mtr.get_row(1)[column("A[0]")], 2.into());
+        assert_eq!(mtr.get_row(1)[column("S(X[0,1])")], 0.into());
+        assert_eq!(mtr.get_rhs(1), 2.into());
+        // Z[1,0] + K[0] + 03*Z[0,1]
+        assert_eq!(
+            terms(3),
+            [
+                (String::from("K[0]"), 1),
+                (String::from("Z[0,1]"), 3),
+                (String::from("Z[1,0]"), 1)
+            ]
+        );
+        // Z[0,1] + K[1] + Z[1,0] + 02*Z[1,1]
+        assert_eq!(mtr.get_row(4)[column("Z[0,1]")], 1.into());
+        assert_eq!(mtr.get_row(4)[column("Z[1,1]")], 2.into());
     }
 
     #[test]
-    fn only_commentary() {
-        let mut global_infos = GlobalInfos::new(String::from("test/only_comments.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+    fn layer_not_square() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             let M = [[1, 2, 3], [4, 5, 6]]\n\
+             Y[*] = M * (M * X[*])\n\
+             Y[*] = M * X[*]\n",
+            &global_infos,
+        );
 
-        assert!(parser_mod.parse_system(&mut global_infos).is_err());
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+        assert_eq!(
+            errs.iter().map(|e| (e.line, e.char_)).collect::<Vec<_>>(),
+            [(3, 13)]
+        );
+        // Y[1] + 04*X[0] + 05*X[1] + 06*X[2]
+        assert_eq!(parser_mod.matrix.len(), 2);
+        let mut row = parser_mod.matrix[1].clone();
+        row.sort();
+        let column = |name: &str| parser_mod.vars_map[&var(name)];
+        let mut expected = vec![
+            (column("Y[1]"), 1),
+            (column("X[0]"), 4),
+            (column("X[1]"), 5),
+            (column("X[2]"), 6),
+        ];
+        expected.sort();
+        assert_eq!(row, expected);
     }
 
     #[test]
-    fn valid_system() {
-        let mut global_infos = GlobalInfos::new(String::from("test/valid.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+    fn layer_diagnostics() {
+        let mut global_infos = GlobalInfos::new(String::new());
+        let mut parser_mod = Parser::from_string(
+            "--system:\n\
+             let M = [[1, 2], [0, 3]]\n\
+             let V = [[1, 2, 3]]\n\
+             let M = [[1]]\n\
+             let N = [[1, 2], [3]]\n\
+             let P = [1, 2]\n\
+             X[*] + Y\n\
+             X[*] = M * V * Y[*]\n\
+             X[*] = M + Y[*]\n\
+             X[*] = M * (Y[*] + Z\n\
+             X[*] = Y*M * Z[*]\n\
+             X[*] = M * Z[*] + V * Y[*]\n\
+             let X = [[1]]\n\
+             X[*] = M * Y[*]\n",
+            &global_infos,
+        );
 
-        assert!(parser_mod.parse_system(&mut global_infos).is_ok());
+        let errs = parser_mod
+            .parse_system_diagnostics(&mut global_infos)
+            .unwrap_err();
+        assert_eq!(
+            errs.iter().map(|e| (e.line, e.char_)).collect::<Vec<_>>(),
+            [
+                (4, 5),
+                (5, 18),
+                (6, 10),
+                (7, 3),
+                (8, 12),
+                (9, 8),
+                (10, 12),
+                (11, 10),
+                (12, 19),
+                (13, 5)
+            ]
+        );
+        assert_eq!(parser_mod.matrix.len(), 2);
     }
 
     #[test]
     fn simple_00() {
         let mut global_infos = GlobalInfos::new(String::from("test/simple_00.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
 
         let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
@@ -735,7 +3217,7 @@ mod tests {
     #[test]
     fn simple_01() {
         let mut global_infos = GlobalInfos::new(String::from("test/simple_01.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
 
         let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
@@ -747,7 +3229,7 @@ mod tests {
     #[test]
     fn simple_02() {
         let mut global_infos = GlobalInfos::new(String::from("test/simple_02.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
 
         let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
@@ -759,7 +3241,7 @@ mod tests {
     #[test]
     fn simple_03() {
         let mut global_infos = GlobalInfos::new(String::from("test/simple_03.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
 
         let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
@@ -771,7 +3253,7 @@ mod tests {
     #[test]
     fn simple_04() {
         let mut global_infos = GlobalInfos::new(String::from("test/simple_04.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
 
         let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
@@ -783,7 +3265,7 @@ mod tests {
     #[test]
     fn simple_05() {
         let mut global_infos = GlobalInfos::new(String::from("test/simple_05.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
 
         let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
@@ -795,25 +3277,21 @@ mod tests {
     #[test]
     fn simple_06() {
         let mut global_infos = GlobalInfos::new(String::from("test/simple_06.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
 
         let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
         assert_eq!(mtr.get_row_number(), 2);
-        assert_eq!(
-            mtr.get_row(0),
-            [1.into(), 1.into(), 2.into(), 0.into(), 0.into()]
-        );
-        assert_eq!(
-            mtr.get_row(1),
-            [0.into(), 1.into(), 3.into(), 1.into(), 20.into()]
-        );
+        assert_eq!(mtr.get_row(0), [1.into(), 1.into(), 2.into(), 0.into()]);
+        assert_eq!(mtr.get_row(1), [0.into(), 1.into(), 3.into(), 1.into()]);
+        assert_eq!(mtr.get_rhs(0), 0.into());
+        assert_eq!(mtr.get_rhs(1), 20.into());
     }
 
     #[test]
     fn simple_07() {
         let mut global_infos = GlobalInfos::new(String::from("test/simple_07.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
 
         let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
@@ -825,7 +3303,7 @@ mod tests {
     #[test]
     fn complex_00() {
         let mut global_infos = GlobalInfos::new(String::from("test/complex_00.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
 
         let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
@@ -901,7 +3379,7 @@ mod tests {
     #[test]
     fn complex_01() {
         let mut global_infos = GlobalInfos::new(String::from("test/complex_01.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
 
         let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
@@ -977,7 +3455,7 @@ mod tests {
     #[test]
     fn complex_02() {
         let mut global_infos = GlobalInfos::new(String::from("test/complex_02.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
 
         let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
@@ -1070,7 +3548,7 @@ mod tests {
     #[test]
     fn complex_03() {
         let mut global_infos = GlobalInfos::new(String::from("test/complex_03.eqs"));
-        let mut parser_mod = Parser::new(&global_infos);
+        let mut parser_mod = Parser::new(&global_infos).unwrap();
 
         let mtr = parser_mod.parse_system(&mut global_infos).unwrap();
 
@@ -1079,85 +3557,85 @@ mod tests {
         assert_eq!(
             /* line 3 */
             mtr.get_row(0),
-            [vec![1.into(); 3], vec![0.into(); 114]].concat()
+            [vec![1.into(); 3], vec![0.into(); 113]].concat()
         );
 
         assert_eq!(
             /* line 4 */
             mtr.get_row(1),
-            [vec![0.into(); 3], vec![1.into(); 3], vec![0.into(); 111]].concat()
+            [vec![0.into(); 3], vec![1.into(); 3], vec![0.into(); 110]].concat()
         );
 
         assert_eq!(
             /* line 5 */
             mtr.get_row(2),
-            [vec![0.into(); 6], vec![1.into(); 3], vec![0.into(); 108]].concat()
+            [vec![0.into(); 6], vec![1.into(); 3], vec![0.into(); 107]].concat()
         );
 
         assert_eq!(
             /* line 6 */
             mtr.get_row(3),
-            [vec![0.into(); 9], vec![1.into(); 3], vec![0.into(); 105]].concat()
+            [vec![0.into(); 9], vec![1.into(); 3], vec![0.into(); 104]].concat()
         );
         assert_eq!(
             /* line 7 */
             mtr.get_row(4),
-            [vec![0.into(); 12], vec![1.into(); 3], vec![0.into(); 102]].concat()
+            [vec![0.into(); 12], vec![1.into(); 3], vec![0.into(); 101]].concat()
         );
         assert_eq!(
             /* line 8 */
             mtr.get_row(5),
-            [vec![0.into(); 15], vec![1.into(); 3], vec![0.into(); 99]].concat()
+            [vec![0.into(); 15], vec![1.into(); 3], vec![0.into(); 98]].concat()
         );
         assert_eq!(
             /* line 9 */
             mtr.get_row(6),
-            [vec![0.into(); 18], vec![1.into(); 3], vec![0.into(); 96]].concat()
+            [vec![0.into(); 18], vec![1.into(); 3], vec![0.into(); 95]].concat()
         );
         assert_eq!(
             /* line 10 */
             mtr.get_row(7),
-            [vec![0.into(); 21], vec![1.into(); 3], vec![0.into(); 93]].concat()
+            [vec![0.into(); 21], vec![1.into(); 3], vec![0.into(); 92]].concat()
         );
         assert_eq!(
             /* line 11 */
             mtr.get_row(8),
-            [vec![0.into(); 24], vec![1.into(); 3], vec![0.into(); 90]].concat()
+            [vec![0.into(); 24], vec![1.into(); 3], vec![0.into(); 89]].concat()
         );
         assert_eq!(
             /* line 12 */
             mtr.get_row(9),
-            [vec![0.into(); 27], vec![1.into(); 3], vec![0.into(); 87]].concat()
+            [vec![0.into(); 27], vec![1.into(); 3], vec![0.into(); 86]].concat()
         );
         assert_eq!(
             /* line 13 */
             mtr.get_row(10),
-            [vec![0.into(); 30], vec![1.into(); 3], vec![0.into(); 84]].concat()
+            [vec![0.into(); 30], vec![1.into(); 3], vec![0.into(); 83]].concat()
         );
         assert_eq!(
             /* line 14 */
             mtr.get_row(11),
-            [vec![0.into(); 33], vec![1.into(); 3], vec![0.into(); 81]].concat()
+            [vec![0.into(); 33], vec![1.into(); 3], vec![0.into(); 80]].concat()
         );
         assert_eq!(
             /* line 15 */
             mtr.get_row(12),
-            [vec![0.into(); 36], vec![1.into(); 3], vec![0.into(); 78]].concat()
+            [vec![0.into(); 36], vec![1.into(); 3], vec![0.into(); 77]].concat()
         );
         assert_eq!(
             /* line 16 */
             mtr.get_row(13),
-            [vec![0.into(); 39], vec![1.into(); 3], vec![0.into(); 75]].concat()
+            [vec![0.into(); 39], vec![1.into(); 3], vec![0.into(); 74]].concat()
         );
         assert_eq!(
             /* line 17 */
             mtr.get_row(14),
-            [vec![0.into(); 42], vec![1.into(); 3], vec![0.into(); 72]].concat()
+            [vec![0.into(); 42], vec![1.into(); 3], vec![0.into(); 71]].concat()
         );
         assert_eq!(
             /* line 18 */
             mtr.get_row(15),
-            [vec![0.into(); 45], vec![1.into(); 3], vec![0.into(); 69]].concat()
+            [vec![0.into(); 45], vec![1.into(); 3], vec![0.into(); 68]].concat()
         );
         assert_eq!(
             /* line 19 */
@@ -1166,7 +3644,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 47],
                 vec![2.into(), 3.into(), 1.into(), 1.into()],
-                vec![0.into(); 65]
+                vec![0.into(); 64]
             ]
             .concat()
         );
@@ -1178,7 +3656,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 48],
                 vec![2.into(), 3.into(), 1.into(), 1.into()],
-                vec![0.into(); 61]
+                vec![0.into(); 60]
             ]
             .concat()
         );
@@ -1190,7 +3668,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 49],
                 vec![2.into(), 3.into(), 1.into(), 1.into()],
-                vec![0.into(); 57]
+                vec![0.into(); 56]
             ]
             .concat()
         );
@@ -1202,7 +3680,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 50],
                 vec![2.into(), 3.into(), 1.into(), 1.into()],
-                vec![0.into(); 53]
+                vec![0.into(); 52]
             ]
             .concat()
         );
@@ -1214,7 +3692,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 35],
                 vec![1.into(), 2.into(), 3.into(), 1.into()],
-                vec![0.into(); 65]
+                vec![0.into(); 64]
             ]
             .concat()
         );
@@ -1226,7 +3704,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 36],
                 vec![1.into(), 2.into(), 3.into(), 1.into()],
-                vec![0.into(); 61]
+                vec![0.into(); 60]
             ]
             .concat()
         );
@@ -1238,7 +3716,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 37],
                 vec![1.into(), 2.into(), 3.into(), 1.into()],
-                vec![0.into(); 57]
+                vec![0.into(); 56]
             ]
             .concat()
         );
@@ -1250,7 +3728,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 38],
                 vec![1.into(), 2.into(), 3.into(), 1.into()],
-                vec![0.into(); 53]
+                vec![0.into(); 52]
             ]
             .concat()
         );
@@ -1262,7 +3740,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 23],
                 vec![1.into(), 1.into(), 2.into(), 3.into()],
-                vec![0.into(); 65]
+                vec![0.into(); 64]
             ]
             .concat()
         );
@@ -1274,7 +3752,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 24],
                 vec![1.into(), 1.into(), 2.into(), 3.into()],
-                vec![0.into(); 61]
+                vec![0.into(); 60]
             ]
             .concat()
         );
@@ -1286,7 +3764,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 25],
                 vec![1.into(), 1.into(), 2.into(), 3.into()],
-                vec![0.into(); 57]
+                vec![0.into(); 56]
             ]
             .concat()
         );
@@ -1298,7 +3776,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 26],
                 vec![1.into(), 1.into(), 2.into(), 3.into()],
-                vec![0.into(); 53]
+                vec![0.into(); 52]
             ]
             .concat()
         );
@@ -1310,7 +3788,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 11],
                 vec![3.into(), 1.into(), 1.into(), 2.into()],
-                vec![0.into(); 65]
+                vec![0.into(); 64]
             ]
             .concat()
         );
@@ -1322,7 +3800,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 12],
                 vec![3.into(), 1.into(), 1.into(), 2.into()],
-                vec![0.into(); 61]
+                vec![0.into(); 60]
             ]
             .concat()
         );
@@ -1334,7 +3812,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 13],
                 vec![3.into(), 1.into(), 1.into(), 2.into()],
-                vec![0.into(); 57]
+                vec![0.into(); 56]
             ]
             .concat()
         );
@@ -1346,89 +3824,89 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 14],
                 vec![3.into(), 1.into(), 1.into(), 2.into()],
-                vec![0.into(); 53]
+                vec![0.into(); 52]
             ]
             .concat()
         );
         assert_eq!(
             /* line 35 */
             mtr.get_row(32),
-            [vec![0.into(); 64], vec![1.into(); 3], vec![0.into(); 50]].concat()
+            [vec![0.into(); 64], vec![1.into(); 3], vec![0.into(); 49]].concat()
         );
         assert_eq!(
             /* line 36 */
             mtr.get_row(33),
-            [vec![0.into(); 67], vec![1.into(); 3], vec![0.into(); 47]].concat()
+            [vec![0.into(); 67], vec![1.into(); 3], vec![0.into(); 46]].concat()
         );
         assert_eq!(
             /* line 37 */
             mtr.get_row(34),
-            [vec![0.into(); 70], vec![1.into(); 3], vec![0.into(); 44]].concat()
+            [vec![0.into(); 70], vec![1.into(); 3], vec![0.into(); 43]].concat()
         );
         assert_eq!(
             /* line 38 */
             mtr.get_row(35),
-            [vec![0.into(); 73], vec![1.into(); 3], vec![0.into(); 41]].concat()
+            [vec![0.into(); 73], vec![1.into(); 3], vec![0.into(); 40]].concat()
         );
         assert_eq!(
             /* line 39 */
             mtr.get_row(36),
-            [vec![0.into(); 76], vec![1.into(); 3], vec![0.into(); 38]].concat()
+            [vec![0.into(); 76], vec![1.into(); 3], vec![0.into(); 37]].concat()
         );
         assert_eq!(
             /* line 40 */
             mtr.get_row(37),
-            [vec![0.into(); 79], vec![1.into(); 3], vec![0.into(); 35]].concat()
+            [vec![0.into(); 79], vec![1.into(); 3], vec![0.into(); 34]].concat()
         );
         assert_eq!(
             /* line 41 */
             mtr.get_row(38),
-            [vec![0.into(); 82], vec![1.into(); 3], vec![0.into(); 32]].concat()
+            [vec![0.into(); 82], vec![1.into(); 3], vec![0.into(); 31]].concat()
         );
         assert_eq!(
             /* line 42 */
             mtr.get_row(39),
-            [vec![0.into(); 85], vec![1.into(); 3], vec![0.into(); 29]].concat()
+            [vec![0.into(); 85], vec![1.into(); 3], vec![0.into(); 28]].concat()
         );
         assert_eq!(
             /* line 43 */
             mtr.get_row(40),
-            [vec![0.into(); 88], vec![1.into(); 3], vec![0.into(); 26]].concat()
+            [vec![0.into(); 88], vec![1.into(); 3], vec![0.into(); 25]].concat()
         );
         assert_eq!(
             /* line 44 */
             mtr.get_row(41),
-            [vec![0.into(); 91], vec![1.into(); 3], vec![0.into(); 23]].concat()
+            [vec![0.into(); 91], vec![1.into(); 3], vec![0.into(); 22]].concat()
         );
         assert_eq!(
             /* line 45 */
             mtr.get_row(42),
-            [vec![0.into(); 94], vec![1.into(); 3], vec![0.into(); 20]].concat()
+            [vec![0.into(); 94], vec![1.into(); 3], vec![0.into(); 19]].concat()
         );
         assert_eq!(
             /* line 46 */
             mtr.get_row(43),
-            [vec![0.into(); 97], vec![1.into(); 3], vec![0.into(); 17]].concat()
+            [vec![0.into(); 97], vec![1.into(); 3], vec![0.into(); 16]].concat()
         );
         assert_eq!(
             /* line 47 */
             mtr.get_row(44),
-            [vec![0.into(); 100], vec![1.into(); 3], vec![0.into(); 14]].concat()
+            [vec![0.into(); 100], vec![1.into(); 3], vec![0.into(); 13]].concat()
         );
         assert_eq!(
             /* line 48 */
             mtr.get_row(45),
-            [vec![0.into(); 103], vec![1.into(); 3], vec![0.into(); 11]].concat()
+            [vec![0.into(); 103], vec![1.into(); 3], vec![0.into(); 10]].concat()
         );
         assert_eq!(
             /* line 49 */
             mtr.get_row(46),
-            [vec![0.into(); 106], vec![1.into(); 3], vec![0.into(); 8]].concat()
+            [vec![0.into(); 106], vec![1.into(); 3], vec![0.into(); 7]].concat()
         );
         assert_eq!(
             /* line 50 */
             mtr.get_row(47),
-            [vec![0.into(); 109], vec![1.into(); 3], vec![0.into(); 5]].concat()
+            [vec![0.into(); 109], vec![1.into(); 3], vec![0.into(); 4]].concat()
         );
         assert_eq!(
             /* line 51 */
@@ -1438,7 +3916,7 @@ mod tests {
                 vec![1.into(), 0.into(), 0.into(), 1.into()],
                 vec![0.into(); 63],
                 vec![1.into()],
-                vec![0.into(); 6]
+                vec![0.into(); 5]
             ]
             .concat()
         );
@@ -1450,7 +3928,7 @@ mod tests {
                 vec![1.into(), 0.into(), 0.into(), 1.into()],
                 vec![0.into(); 63],
                 vec![1.into()],
-                vec![0.into(); 9]
+                vec![0.into(); 8]
             ]
             .concat()
         );
@@ -1462,7 +3940,7 @@ mod tests {
                 vec![1.into(), 0.into(), 0.into(), 1.into()],
                 vec![0.into(); 63],
                 vec![1.into()],
-                vec![0.into(); 12]
+                vec![0.into(); 11]
             ]
             .concat()
         );
@@ -1476,7 +3954,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 10],
                 vec![1.into()],
-                vec![0.into(); 4]
+                vec![0.into(); 3]
             ]
             .concat()
         );
@@ -1488,7 +3966,7 @@ mod tests {
                 vec![1.into(), 0.into(), 0.into(), 1.into()],
                 vec![0.into(); 63],
                 vec![1.into()],
-                vec![0.into(); 18]
+                vec![0.into(); 17]
             ]
             .concat()
         );
@@ -1500,7 +3978,7 @@ mod tests {
                 vec![1.into(), 0.into(), 0.into(), 1.into()],
                 vec![0.into(); 63],
                 vec![1.into()],
-                vec![0.into(); 21]
+                vec![0.into(); 20]
             ]
             .concat()
         );
@@ -1512,7 +3990,7 @@ mod tests {
                 vec![1.into(), 0.into(), 0.into(), 1.into()],
                 vec![0.into(); 63],
                 vec![1.into()],
-                vec![0.into(); 24]
+                vec![0.into(); 23]
             ]
             .concat()
         );
@@ -1526,7 +4004,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 23],
                 vec![1.into()],
-                vec![0.into(); 3]
+                vec![0.into(); 2]
             ]
             .concat()
         );
@@ -1538,7 +4016,7 @@ mod tests {
                 vec![1.into(), 0.into(), 0.into(), 1.into()],
                 vec![0.into(); 63],
                 vec![1.into()],
-                vec![0.into(); 30],
+                vec![0.into(); 29],
             ]
             .concat()
         );
@@ -1550,7 +4028,7 @@ mod tests {
                 vec![1.into(), 0.into(), 0.into(), 1.into()],
                 vec![0.into(); 63],
                 vec![1.into()],
-                vec![0.into(); 33],
+                vec![0.into(); 32],
             ]
             .concat()
         );
@@ -1562,7 +4040,7 @@ mod tests {
                 vec![1.into(), 0.into(), 0.into(), 1.into()],
                 vec![0.into(); 63],
                 vec![1.into()],
-                vec![0.into(); 36],
+                vec![0.into(); 35],
             ]
             .concat()
         );
@@ -1576,7 +4054,7 @@ mod tests {
                 vec![1.into()],
                 vec![0.into(); 36],
                 vec![1.into()],
-                vec![0.into(); 2]
+                vec![0.into(); 1]
             ]
             .concat()
         );
@@ -1588,7 +4066,7 @@ mod tests {
                 vec![1.into(), 0.into(), 0.into(), 1.into()],
                 vec![0.into(); 63],
                 vec![1.into()],
-                vec![0.into(); 42]
+                vec![0.into(); 41]
             ]
             .concat()
         );
@@ -1600,7 +4078,7 @@ mod tests {
                 vec![1.into(), 0.into(), 0.into(), 1.into()],
                 vec![0.into(); 63],
                 vec![1.into()],
-                vec![0.into(); 45]
+                vec![0.into(); 44]
             ]
             .concat()
         );
@@ -1612,7 +4090,7 @@ mod tests {
                 vec![1.into(), 0.into(), 0.into(), 1.into()],
                 vec![0.into(); 63],
                 vec![1.into()],
-                vec![0.into(); 48]
+                vec![0.into(); 47]
             ]
             .concat()
         );
@@ -1625,9 +4103,11 @@ mod tests {
                 vec![0.into(); 63],
                 vec![1.into()],
                 vec![0.into(); 49],
-                vec![1.into(), 1.into()],
+                vec![1.into()],
             ]
             .concat()
         );
+        assert_eq!(mtr.get_rhs(62), 0.into());
+        assert_eq!(mtr.get_rhs(63), 1.into());
     }
 }
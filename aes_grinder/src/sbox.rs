@@ -0,0 +1,107 @@
+//! Nonlinear functions of the systems, given by their lookup table
+use crate::field::{Field, GF2n};
+use crate::utils::Number;
+
+/// Function from n bits to n bits, declared in the .eqs files as
+///     --sbox S = [0x63, 0x7c, ...]
+///     --sbox Sinv = inverse(S)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sbox {
+    table: Vec<u32>, // 2^n entries
+}
+
+impl Sbox {
+    /// Function of the table, which need 2^n entries below 2^n
+    pub fn new(table: Vec<u32>) -> Result<Self, String> {
+        if table.len() < 2 || !table.len().is_power_of_two() {
+            return Err(format!("sbox need 2^n entries, not {}", table.len()));
+        }
+        if let Some(value) = table.iter().find(|&&value| value as usize >= table.len()) {
+            return Err(format!(
+                "sbox entry {} is out of the {} bits of the sbox",
+                value,
+                table.len().trailing_zeros()
+            ));
+        }
+        Ok(Sbox { table })
+    }
+
+    /// The AES S-box, inverse in GF(2^8) / 0x11b followed by an affine map
+    pub fn aes() -> Self {
+        let table = (0..=255u8)
+            .map(|x| {
+                let b = GF2n::aes()
+                    .inv(Number::from(x))
+                    .map_or(0, |b| b.get_value()) as u8;
+                let s = b
+                    ^ b.rotate_left(1)
+                    ^ b.rotate_left(2)
+                    ^ b.rotate_left(3)
+                    ^ b.rotate_left(4)
+                    ^ 0x63;
+                s as u32
+            })
+            .collect();
+        Sbox { table }
+    }
+
+    pub fn table(&self) -> &[u32] {
+        &self.table
+    }
+
+    /// Number of bits of the inputs and outputs
+    pub fn bits(&self) -> u32 {
+        self.table.len().trailing_zeros()
+    }
+
+    pub fn apply(&self, x: u32) -> u32 {
+        self.table[x as usize]
+    }
+
+    /// Inverse function, only for a permutation
+    pub fn inverse(&self) -> Option<Self> {
+        let mut table = vec![None; self.table.len()];
+        for (x, &y) in self.table.iter().enumerate() {
+            if table[y as usize].replace(x as u32).is_some() {
+                return None;
+            }
+        }
+        Some(Sbox {
+            table: table.into_iter().map(Option::unwrap).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aes() {
+        let s = Sbox::aes();
+
+        assert_eq!(s.bits(), 8);
+        assert_eq!(s.apply(0x00), 0x63);
+        assert_eq!(s.apply(0x01), 0x7c);
+        assert_eq!(s.apply(0x53), 0xed);
+        assert_eq!(s.apply(0xff), 0x16);
+    }
+
+    #[test]
+    fn inverse() {
+        let s = Sbox::aes();
+        let s_inv = s.inverse().unwrap();
+
+        assert_eq!(s_inv.apply(0x63), 0x00);
+        assert!((0..256).all(|x| s_inv.apply(s.apply(x)) == x));
+        assert_eq!(Sbox::new(vec![0, 0, 1, 2]).unwrap().inverse(), None);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Sbox::new(vec![]).is_err());
+        assert!(Sbox::new(vec![0, 1, 2]).is_err());
+        assert!(Sbox::new(vec![0, 4, 1, 2]).is_err());
+        assert_eq!(Sbox::new(vec![1, 0]).unwrap().bits(), 1);
+    }
+}
@@ -0,0 +1,215 @@
+//! Variables of the columns of a matrix, looked up by name and by column
+use crate::variable::Variable;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Column of each variable and variable of each column, kept in sync.
+/// Both directions share one allocation per name.
+/// The clones of the table share it too, as do the tables filled with shared.
+#[derive(Debug, Clone, Default)]
+pub struct VarTable {
+    columns: HashMap<Arc<Variable>, usize>,
+    variables: Vec<Option<Arc<Variable>>>, // None for a column without variable
+}
+
+impl VarTable {
+    pub fn new() -> Self {
+        VarTable::default()
+    }
+
+    /// Number of variables
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Column of the variable
+    pub fn get(&self, var: &Variable) -> Option<&usize> {
+        self.columns.get(var)
+    }
+
+    pub fn contains_key(&self, var: &Variable) -> bool {
+        self.columns.contains_key(var)
+    }
+
+    /// Variable of the column
+    pub fn variable(&self, column: usize) -> Option<&Variable> {
+        self.variables.get(column)?.as_deref()
+    }
+
+    /// The variable as stored in the table, to share it with another one
+    pub fn shared(&self, var: &Variable) -> Option<Arc<Variable>> {
+        self.columns.get_key_value(var).map(|(var, _)| var.clone())
+    }
+
+    /// Put var in column, the previous column of var and the previous
+    /// variable of column are left without variable
+    /// A var already in the table keeps its shared name
+    pub fn insert(&mut self, var: impl Into<Arc<Variable>>, column: usize) {
+        let mut var = var.into();
+        if let Some((shared, previous)) = self.columns.remove_entry(&var) {
+            self.variables[previous] = None;
+            var = shared;
+        }
+        if column >= self.variables.len() {
+            self.variables.resize(column + 1, None);
+        }
+        if let Some(previous) = self.variables[column].replace(var.clone()) {
+            self.columns.remove(&previous);
+        }
+        self.columns.insert(var, column);
+    }
+
+    /// Exchange the variables of two columns
+    pub fn swap(&mut self, col1: usize, col2: usize) {
+        let len = self.variables.len().max(col1 + 1).max(col2 + 1);
+        self.variables.resize(len, None);
+        self.variables.swap(col1, col2);
+        for column in [col1, col2] {
+            if let Some(var) = &self.variables[column] {
+                *self.columns.get_mut(var).unwrap() = column;
+            }
+        }
+    }
+
+    /// Remove the column and its variable, the next columns move left
+    /// Costs one update per next column, as moving the column in the matrix
+    pub fn remove_column(&mut self, column: usize) -> Option<Variable> {
+        if column >= self.variables.len() {
+            return None;
+        }
+        let var = self.variables.remove(column);
+        if let Some(var) = &var {
+            self.columns.remove(var);
+        }
+        for (j, next) in self.variables.iter().enumerate().skip(column) {
+            if let Some(next) = next {
+                *self.columns.get_mut(next).unwrap() = j;
+            }
+        }
+        var.map(|var| (*var).clone())
+    }
+
+    pub fn clear(&mut self) {
+        self.columns.clear();
+        self.variables.clear();
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Variable> {
+        self.columns.keys().map(|var| var.as_ref())
+    }
+
+    /// Variables with their column, in no order
+    pub fn iter(&self) -> impl Iterator<Item = (&Variable, &usize)> {
+        self.columns
+            .iter()
+            .map(|(var, column)| (var.as_ref(), column))
+    }
+}
+
+/// Same variables in the same columns
+impl PartialEq for VarTable {
+    fn eq(&self, other: &Self) -> bool {
+        self.columns == other.columns
+    }
+}
+
+impl std::ops::Index<&Variable> for VarTable {
+    type Output = usize;
+
+    fn index(&self, var: &Variable) -> &usize {
+        &self.columns[var]
+    }
+}
+
+impl<'a> IntoIterator for &'a VarTable {
+    type Item = (&'a Variable, &'a usize);
+    type IntoIter = Box<dyn Iterator<Item = Self::Item> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+#[cfg(test)]
+impl From<HashMap<Variable, usize>> for VarTable {
+    fn from(vars_map: HashMap<Variable, usize>) -> Self {
+        let mut table = VarTable::new();
+        for (var, column) in vars_map {
+            table.insert(var, column);
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variable::var;
+
+    fn table() -> VarTable {
+        VarTable::from(HashMap::from([(var("A"), 0), (var("B"), 1), (var("C"), 2)]))
+    }
+
+    #[test]
+    fn both_ways() {
+        let table = table();
+
+        assert_eq!(table.len(), 3);
+        assert_eq!(table[&var("B")], 1);
+        assert_eq!(table.get(&var("D")), None);
+        assert_eq!(table.variable(2), Some(&var("C")));
+        assert_eq!(table.variable(3), None);
+    }
+
+    #[test]
+    fn swap() {
+        let mut table = table();
+        table.swap(0, 2);
+
+        assert_eq!(table[&var("A")], 2);
+        assert_eq!(table[&var("C")], 0);
+        assert_eq!(table.variable(0), Some(&var("C")));
+        assert_eq!(table.variable(1), Some(&var("B")));
+    }
+
+    #[test]
+    fn remove_column() {
+        let mut table = table();
+
+        assert_eq!(table.remove_column(1), Some(var("B")));
+        assert_eq!(table.remove_column(5), None);
+        assert_eq!(
+            table,
+            VarTable::from(HashMap::from([(var("A"), 0), (var("C"), 1)]))
+        );
+        assert_eq!(table.variable(1), Some(&var("C")));
+        assert_eq!(table.variable(2), None);
+    }
+
+    #[test]
+    fn insert() {
+        let mut table = table();
+
+        // B moves to the column of A
+        table.insert(var("B"), 0);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.variable(0), Some(&var("B")));
+        assert_eq!(table.variable(1), None);
+        assert!(!table.contains_key(&var("A")));
+
+        // Shared with a copy
+        let shared = table.shared(&var("C")).unwrap();
+        let mut copy = VarTable::new();
+        copy.insert(shared.clone(), 0);
+        assert!(Arc::ptr_eq(&shared, &copy.shared(&var("C")).unwrap()));
+
+        // Moved, with the same name
+        copy.insert(var("C"), 1);
+        assert_eq!(copy.variable(0), None);
+        assert!(Arc::ptr_eq(&shared, &copy.shared(&var("C")).unwrap()));
+    }
+}
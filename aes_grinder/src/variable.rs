@@ -0,0 +1,293 @@
+//! Typed variables of the equation systems
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use strum::EnumIter;
+
+/// Variable of a system, written in the .eqs files as
+///     base_round[i,j]     W_0[1,2], K_1[0,0], c0
+///     F(variable)         S(X_0[0,0])
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Variable {
+    base: String,
+    round: Option<usize>,
+    indices: Vec<usize>,
+    function: Option<String>, // nonlinear function applied on the variable
+}
+
+impl Variable {
+    /// Variable from an identifier such as W_0 and its indices
+    pub fn new(ident: &str, indices: Vec<usize>) -> Self {
+        // A number written without leading zero after the last _ is the round
+        let (base, round) = match ident.rsplit_once('_') {
+            Some((base, round))
+                if !base.is_empty()
+                    && !round.is_empty()
+                    && round.bytes().all(|c| c.is_ascii_digit())
+                    && (round == "0" || !round.starts_with('0')) =>
+            {
+                match round.parse() {
+                    Ok(round) => (base, Some(round)),
+                    Err(_) => (ident, None),
+                }
+            }
+            _ => (ident, None),
+        };
+
+        Variable {
+            base: base.to_string(),
+            round,
+            indices,
+            function: None,
+        }
+    }
+
+    /// The variable seen through the nonlinear function
+    pub fn apply(&self, function: &str) -> Self {
+        Variable {
+            function: Some(function.to_string()),
+            ..self.clone()
+        }
+    }
+
+    pub fn is_nonlinear(&self) -> bool {
+        self.function.is_some()
+    }
+
+    pub fn round(&self) -> Option<usize> {
+        self.round
+    }
+
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Name of the nonlinear function applied on the variable
+    pub fn function(&self) -> Option<&str> {
+        self.function.as_deref()
+    }
+
+    /// Variable the nonlinear function is applied on, itself either
+    pub fn input(&self) -> Self {
+        Variable {
+            function: None,
+            ..self.clone()
+        }
+    }
+
+    /// The variable without indices, a pattern of every indices
+    pub fn without_indices(&self) -> Self {
+        Variable {
+            indices: vec![],
+            ..self.clone()
+        }
+    }
+
+    /// True if the variable is an instance of pattern, a pattern without
+    /// round or indices stands for every round or indices
+    ///     K matches K_1[0,0], K_1 matches K_1[0,0], K_1[0,0] only itself
+    pub fn matches(&self, pattern: &Variable) -> bool {
+        self.base == pattern.base
+            && self.function == pattern.function
+            && (pattern.round.is_none() || self.round == pattern.round)
+            && (pattern.indices.is_empty() || self.indices == pattern.indices)
+    }
+}
+
+/// Role of a variable in the attack, declared in the .eqs files as
+///     --plaintext: P
+///     --key: K_0 K_1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter)]
+pub enum Role {
+    Plaintext,  // known
+    Ciphertext, // known
+    Key,
+    State,  // internal state
+    Target, // unknown to recover, never eliminated
+}
+
+impl Role {
+    /// Role of an undeclared variable, guessed from its name
+    pub fn guess(var: &Variable) -> Self {
+        match var.base.as_str() {
+            "P" => Role::Plaintext,
+            "C" => Role::Ciphertext,
+            base if base.starts_with('K') => Role::Key,
+            _ => Role::State,
+        }
+    }
+
+    /// Value known by the attacker
+    pub fn is_known(self) -> bool {
+        matches!(self, Role::Plaintext | Role::Ciphertext)
+    }
+}
+
+/// Name of the role in the .eqs files
+impl Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            Role::Plaintext => "plaintext",
+            Role::Ciphertext => "ciphertext",
+            Role::Key => "key",
+            Role::State => "state",
+            Role::Target => "target",
+        })
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        match str {
+            "plaintext" => Ok(Role::Plaintext),
+            "ciphertext" => Ok(Role::Ciphertext),
+            "key" => Ok(Role::Key),
+            "state" => Ok(Role::State),
+            "target" => Ok(Role::Target),
+            _ => Err(format!("unknown role '{}'", str)),
+        }
+    }
+}
+
+impl Display for Variable {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(function) = &self.function {
+            write!(f, "{}(", function)?;
+        }
+        write!(f, "{}", self.base)?;
+        if let Some(round) = self.round {
+            write!(f, "_{}", round)?;
+        }
+        if !self.indices.is_empty() {
+            let indices: Vec<String> = self.indices.iter().map(|i| i.to_string()).collect();
+            write!(f, "[{}]", indices.join(","))?;
+        }
+        if self.function.is_some() {
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+}
+
+/// Same as the name in the systems, easier to read in debug outputs
+impl Debug for Variable {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self.to_string())
+    }
+}
+
+pub fn is_ident(str: &str) -> bool {
+    str.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && str.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl FromStr for Variable {
+    type Err = String;
+
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        let str = str.trim();
+
+        if let Some(call) = str.strip_suffix(')') {
+            let Some((function, input)) = call.split_once('(') else {
+                return Err(format!("unbalanced parenthesis in '{}'", str));
+            };
+            let input: Variable = input.parse()?;
+            if input.is_nonlinear() || !is_ident(function.trim()) {
+                return Err(format!("invalid variable '{}'", str));
+            }
+            return Ok(input.apply(function.trim()));
+        }
+
+        let (ident, indices) = match str.split_once('[') {
+            Some((ident, indices)) => {
+                let Some(indices) = indices.strip_suffix(']') else {
+                    return Err(format!("indices of '{}' need to be closed by ']'", str));
+                };
+                let indices = indices
+                    .split(',')
+                    .map(|i| i.trim().parse::<usize>())
+                    .collect::<Result<Vec<usize>, _>>()
+                    .map_err(|e| format!("invalid index in '{}' :: {}", str, e))?;
+                (ident.trim(), indices)
+            }
+            None => (str, vec![]),
+        };
+        if !is_ident(ident) {
+            return Err(format!("invalid variable '{}'", str));
+        }
+
+        Ok(Variable::new(ident, indices))
+    }
+}
+
+/// Variable from its name, panic if it is not valid
+#[cfg(test)]
+pub fn var(name: &str) -> Variable {
+    name.parse().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn display_parsed() {
+        for name in ["W_0[1,2]", "S(X_0[0,0])", "c0", "var_01", "X_10", "K", "_"] {
+            assert_eq!(var(name).to_string(), name);
+        }
+        assert_eq!(var(" S ( X_0 [0, 01] ) ").to_string(), "S(X_0[0,1])");
+    }
+
+    #[test]
+    fn round() {
+        assert_eq!(var("X_1").round, Some(1));
+        assert_eq!(var("X_10").round, Some(10));
+        assert_eq!(var("KC_0").base, "KC");
+        assert_eq!(var("var_01").round, None);
+        assert_ne!(var("X_1"), var("X_10"));
+    }
+
+    #[test]
+    fn nonlinear() {
+        let sx = var("S(X_1[0,0])");
+
+        assert!(sx.is_nonlinear());
+        assert_eq!(sx.input(), var("X_1[0,0]"));
+        assert_ne!(sx.input(), var("X_10[0,0]"));
+        assert_eq!(var("X_1[0,0]").apply("S"), sx);
+    }
+
+    #[test]
+    fn known() {
+        assert!(Role::guess(&var("C[0,0]")).is_known());
+        assert!(Role::guess(&var("P_1[0,0]")).is_known());
+        assert_eq!(Role::guess(&var("KC_0[0,0]")), Role::Key);
+        assert_eq!(Role::guess(&var("X_0[0,0]")), Role::State);
+        assert!(!Role::Target.is_known());
+        for role in Role::iter() {
+            assert_eq!(role.to_string().parse(), Ok(role));
+        }
+    }
+
+    #[test]
+    fn matches() {
+        let k = var("K_1[0,2]");
+
+        assert!(k.matches(&var("K")));
+        assert!(k.matches(&var("K_1")));
+        assert!(k.matches(&var("K_1[0,2]")));
+        assert!(!k.matches(&var("K_0")));
+        assert!(!k.matches(&var("K_1[0,0]")));
+        assert!(!k.matches(&var("KC")));
+        assert!(!k.apply("S").matches(&var("K")));
+    }
+
+    #[test]
+    fn invalid() {
+        for name in ["", "0a", "X[0", "X[a]", "S(T(X))", "S(X", "S X"] {
+            assert!(name.parse::<Variable>().is_err(), "{}", name);
+        }
+    }
+}
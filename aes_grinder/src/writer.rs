@@ -0,0 +1,347 @@
+//! Canonical .eqs text of the systems
+use crate::field::GF2n;
+use crate::matrix::{Equation, Matrix};
+use crate::parser::Parser;
+use crate::sbox::Sbox;
+use crate::variable::{Role, Variable};
+use crate::GlobalInfos;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use strum::IntoEnumIterator;
+
+/// Text of the sections as a .eqs file, parsed back to the same systems
+///     field, sboxes and roles first, then the rows of each section with their
+///     terms sorted by variable and coefficients in hexadecimal
+/// Rows are grouped by round under a comment if group_by_round,
+/// the round of a row being the last round of its variables
+pub fn write_system(sections: &[(String, Matrix)], group_by_round: bool) -> String {
+    let mut text = String::new();
+
+    // GF(2^8) / 0x11b is the field by default
+    if let Some((_, matrix)) = sections.first() {
+        if *matrix.field() != GF2n::aes() {
+            writeln!(text, "--field: {}", matrix.field()).unwrap();
+        }
+    }
+
+    // Declarations apply to every section
+    let mut sboxes: BTreeMap<&str, &Sbox> = BTreeMap::new();
+    let mut roles: BTreeMap<&Variable, Role> = BTreeMap::new();
+    for (_, matrix) in sections {
+        sboxes.extend(
+            matrix
+                .get_sboxes()
+                .iter()
+                .map(|(name, sbox)| (name.as_str(), sbox)),
+        );
+        roles.extend(matrix.get_roles().iter().map(|(var, role)| (var, *role)));
+    }
+
+    for (name, sbox) in sboxes {
+        // S is the AES S-box unless declared
+        if name == "S" && *sbox == Sbox::aes() {
+            continue;
+        }
+        let table: Vec<String> = sbox.table().iter().map(|v| format!("{:#04x}", v)).collect();
+        writeln!(text, "--sbox {} = [{}]", name, table.join(", ")).unwrap();
+    }
+    for role in Role::iter() {
+        let vars: Vec<String> = roles
+            .iter()
+            .filter(|(_, r)| **r == role)
+            .map(|(var, _)| var.to_string())
+            .collect();
+        if !vars.is_empty() {
+            writeln!(text, "--{}: {}", role, vars.join(" ")).unwrap();
+        }
+    }
+
+    for (name, matrix) in sections {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        writeln!(text, "--{}:", name).unwrap();
+        write_rows(&mut text, matrix, group_by_round);
+    }
+    text
+}
+
+/// Replace the file by the text of its sections
+/// The file is left unchanged if it has comments or if its rows are expanded
+/// from constructs the text can not keep, as for loops or included files
+pub fn format_file(filename: &str, group_by_round: bool) -> Result<(), String> {
+    let mut globals = GlobalInfos::new(filename.to_owned());
+    let mut parser_mod = Parser::new(&globals).map_err(|e| e.to_string())?;
+    let sections = parser_mod.parse_sections(&mut globals);
+    for warning in parser_mod.take_warnings() {
+        eprintln!("{}", warning);
+    }
+    let sections = sections.map_err(|e| e.to_string())?;
+
+    if !parser_mod.expanded().is_empty() {
+        let constructs: Vec<String> = parser_mod
+            .expanded()
+            .iter()
+            .map(|construct| construct.to_string())
+            .collect();
+        return Err(format!(
+            "{}\n{} is left unchanged",
+            constructs.join("\n"),
+            filename
+        ));
+    }
+    std::fs::write(filename, write_system(&sections, group_by_round))
+        .map_err(|e| format!("{}: {}", filename, e))
+}
+
+fn write_rows(text: &mut String, matrix: &Matrix, group_by_round: bool) {
+    let mut columns: Vec<(&Variable, usize)> = matrix
+        .get_vars_map()
+        .iter()
+        .map(|(var, &column)| (var, column))
+        .collect();
+    columns.sort();
+
+    let mut rows: Vec<(Option<usize>, String)> = (0..matrix.get_row_number())
+        .map(|i| {
+            let row = matrix.get_row(i);
+            let terms: Vec<(&Variable, u16)> = columns
+                .iter()
+                .map(|&(var, column)| (var, row[column].get_value()))
+                .filter(|&(_, coef)| coef != 0)
+                .collect();
+            let round = terms.iter().filter_map(|(var, _)| var.round()).max();
+            (round, row_text(&terms, matrix.get_rhs(i).get_value()))
+        })
+        .collect();
+
+    if group_by_round {
+        // rows without round first
+        rows.sort_by_key(|(round, _)| *round);
+    }
+
+    let mut group: Option<Option<usize>> = None;
+    for (round, equation) in rows {
+        if group_by_round && group != Some(round) {
+            if let Some(round) = round {
+                writeln!(text, "# round {}", round).unwrap();
+            }
+            group = Some(round);
+        }
+        writeln!(text, "{}", equation).unwrap();
+    }
+}
+
+/// Text of an equation as a row of a .eqs file
+pub fn write_equation(equation: &Equation) -> String {
+    let terms: Vec<(&Variable, u16)> = equation
+        .terms
+        .iter()
+        .map(|(var, coef)| (var, coef.get_value()))
+        .collect();
+    row_text(&terms, equation.rhs.get_value())
+}
+
+fn row_text(terms: &[(&Variable, u16)], rhs: u16) -> String {
+    let mut equation = terms
+        .iter()
+        .map(|&(var, coef)| match coef {
+            1 => var.to_string(),
+            _ => format!("{:#04x}*{}", coef, var),
+        })
+        .collect::<Vec<String>>()
+        .join(" + ");
+    // a row without term is the equation 0 = rhs
+    if terms.is_empty() {
+        equation = format!("{:#04x}", rhs);
+    } else if rhs != 0 {
+        write!(equation, " = {:#04x}", rhs).unwrap();
+    }
+    equation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn parse(system: &str) -> Vec<(String, Matrix)> {
+        let mut global_infos = GlobalInfos::new(String::new());
+        Parser::from_string(system, &global_infos)
+            .parse_sections(&mut global_infos)
+            .unwrap()
+    }
+
+    fn parse_file(filename: &str) -> Vec<(String, Matrix)> {
+        let mut global_infos = GlobalInfos::new(filename.to_string());
+        Parser::new(&global_infos)
+            .unwrap()
+            .parse_sections(&mut global_infos)
+            .unwrap()
+    }
+
+    /// Rows of a section as terms and rhs, independent of the column order
+    fn equations(matrix: &Matrix) -> Vec<(HashMap<Variable, u16>, u16)> {
+        (0..matrix.get_row_number())
+            .map(|i| {
+                let terms = matrix
+                    .get_vars_map()
+                    .iter()
+                    .map(|(var, &column)| (var.clone(), matrix.get_row(i)[column].get_value()))
+                    .filter(|&(_, coef)| coef != 0)
+                    .collect();
+                (terms, matrix.get_rhs(i).get_value())
+            })
+            .collect()
+    }
+
+    fn assert_same(sections: &[(String, Matrix)], written: &[(String, Matrix)]) {
+        assert_eq!(sections.len(), written.len());
+        for ((name, matrix), (written_name, written)) in sections.iter().zip(written) {
+            assert_eq!(name, written_name);
+            assert_eq!(equations(matrix), equations(written));
+            assert_eq!(matrix.get_roles(), written.get_roles());
+            assert_eq!(matrix.get_sboxes(), written.get_sboxes());
+        }
+    }
+
+    #[test]
+    fn round_trip_files() {
+        for filename in [
+            "test/simple_00.eqs",
+            "test/complex_01.eqs",
+            "test/sections_00.eqs",
+            "test/include_00.eqs",
+            "equation_system/dp_example.eqs",
+        ] {
+            let sections = parse_file(filename);
+            let text = write_system(&sections, false);
+            let written = parse(&text);
+
+            assert_same(&sections, &written);
+            assert_eq!(write_system(&written, false), text, "{}", filename);
+        }
+    }
+
+    #[test]
+    fn format_expanded() {
+        let filename = "test/format_00.eqs";
+        let before = std::fs::read_to_string(filename).unwrap();
+
+        let error = format_file(filename, false).unwrap_err();
+        for construct in [
+            "line 1 and char 1 :: role pattern K can not be written back",
+            "line 4 and char 1 :: let matrix can not be written back",
+            "line 5 and char 1 :: for loop can not be written back",
+            "line 7 and char 1 :: --include can not be written back",
+        ] {
+            assert!(error.contains(construct), "{}", error);
+        }
+        assert!(error.ends_with("test/format_00.eqs is left unchanged"));
+        assert_eq!(std::fs::read_to_string(filename).unwrap(), before);
+    }
+
+    #[test]
+    fn format_comments() {
+        let path = std::env::temp_dir().join(format!("format_comments_{}.eqs", std::process::id()));
+        let filename = path.to_str().unwrap();
+
+        // comments are refused
+        let commented = "# header\n--system:\nb + a # the last row\n";
+        std::fs::write(&path, commented).unwrap();
+        let error = format_file(filename, false).unwrap_err();
+        assert!(error.contains("line 1 and char 1 :: comment can not be written back"));
+        assert!(error.contains("line 3 and char 7 :: comment can not be written back"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), commented);
+
+        // rewritten without them
+        std::fs::write(&path, "--system:\nb + a\n").unwrap();
+        format_file(filename, false).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "--system:\na + b\n"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn declarations() {
+        // T(x) = x + 3 mod 256
+        let table: Vec<u32> = (0..256).map(|x| (x + 3) % 256).collect();
+        let written: Vec<String> = table.iter().map(|x| format!("{:#04x}", x)).collect();
+        let table: Vec<String> = table.iter().map(|x| x.to_string()).collect();
+        let sections = parse(&format!(
+            "--sbox T = [{}]\n\
+             --key: K\n\
+             --target: K_0[1]\n\
+             --system:\n\
+             Y + 03*T(X[0]) + K_0[1] = 2\n\
+             S(Y) + Y + S(K_0[0])\n\
+             const c = 2\n\
+             T(c) + c\n",
+            table.join(", ")
+        ));
+        let text = write_system(&sections, false);
+
+        assert_eq!(
+            text,
+            format!(
+                "--sbox T = [{}]\n\
+                 --key: K_0[0]\n\
+                 --target: K_0[1]\n\
+                 \n\
+                 --system:\n\
+                 K_0[1] + 0x03*T(X[0]) + Y = 0x02\n\
+                 S(K_0[0]) + Y + S(Y)\n\
+                 0x07\n",
+                written.join(", ")
+            )
+        );
+        assert_same(&sections, &parse(&text));
+    }
+
+    #[test]
+    fn field() {
+        let sections = parse(
+            "--field: GF(2^4) / 0x13\n\
+             --system:\n\
+             0xf*a + 02*(a + b)\n",
+        );
+        let text = write_system(&sections, false);
+
+        assert_eq!(
+            text,
+            "--field: GF(2^4) / 0x13\n\n--system:\n0x0d*a + 0x02*b\n"
+        );
+        assert_eq!(parse(&text)[0].1.field().polynomial(), 0x13);
+        assert_same(&sections, &parse(&text));
+    }
+
+    #[test]
+    fn group_by_round() {
+        let sections = parse(
+            "--system:\n\
+             X_1[0] + K_1[0] + Y\n\
+             X_0[0] + K_0[0]\n\
+             Y + Z\n\
+             X_1[0] + X_0[0]\n",
+        );
+        let text = write_system(&sections, true);
+
+        assert_eq!(
+            text,
+            "--system:\n\
+             Y + Z\n\
+             # round 0\n\
+             K_0[0] + X_0[0]\n\
+             # round 1\n\
+             K_1[0] + X_1[0] + Y\n\
+             X_0[0] + X_1[0]\n"
+        );
+        let rows = equations(&sections[0].1);
+        assert_eq!(
+            equations(&parse(&text)[0].1),
+            [2, 1, 0, 3].map(|i| rows[i].clone())
+        );
+    }
+}